@@ -0,0 +1,137 @@
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// On-disk cache of the last response of each channel
+pub struct FeedCache {
+    dir: PathBuf,
+}
+
+/// Validators and fetch time stored next to a cached response body
+#[derive(Debug, Deserialize, Serialize)]
+struct CacheMeta {
+    url: String,
+    /// Url the body was actually downloaded from, if the channel url
+    /// pointed at a web page that links to the feed
+    #[serde(default)]
+    feed_url: Option<String>,
+    #[serde(default)]
+    etag: Option<String>,
+    #[serde(default)]
+    last_modified: Option<String>,
+    /// Seconds since the unix epoch
+    fetched_at: u64,
+}
+
+/// A cached response body of a channel
+#[derive(Debug)]
+pub struct CacheEntry {
+    /// Url the body was downloaded from
+    pub feed_url: String,
+    /// `ETag` header of the cached response
+    pub etag: Option<String>,
+    /// `Last-Modified` header of the cached response
+    pub last_modified: Option<String>,
+    /// When the response was last fetched or revalidated
+    pub fetched_at: SystemTime,
+    /// The raw response body
+    pub body: Vec<u8>,
+}
+
+impl FeedCache {
+    /// Open the cache in the system cache directory
+    pub fn open() -> FeedCache {
+        let dir = dirs::cache_dir()
+            .map(|mut d| {
+                d.push("rssget");
+                d
+            })
+            .expect("could not determine system cache directory");
+        FeedCache { dir }
+    }
+
+    /// Open a cache kept in `dir`
+    #[cfg(test)]
+    pub fn in_dir(dir: PathBuf) -> FeedCache {
+        FeedCache { dir }
+    }
+
+    /// Look up the cached response for `url`.
+    /// Missing or unreadable cache files are treated as a cache miss.
+    pub fn get(&self, url: &str) -> Option<CacheEntry> {
+        let (meta_path, body_path) = self.paths(url);
+        let meta_file = OpenOptions::new().read(true).open(meta_path).ok()?;
+        let meta: CacheMeta = serde_yaml::from_reader(&meta_file).ok()?;
+        // guard against hash collisions
+        if meta.url != url {
+            return None;
+        }
+        Some(CacheEntry {
+            feed_url: meta.feed_url.unwrap_or(meta.url),
+            etag: meta.etag,
+            last_modified: meta.last_modified,
+            fetched_at: UNIX_EPOCH + Duration::from_secs(meta.fetched_at),
+            body: fs::read(body_path).ok()?,
+        })
+    }
+
+    /// Store the response for `url`, downloaded from `feed_url`, replacing any previous one
+    pub fn put(
+        &self,
+        url: &str,
+        feed_url: &str,
+        etag: Option<String>,
+        last_modified: Option<String>,
+        body: &[u8],
+    ) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let (meta_path, body_path) = self.paths(url);
+        let meta = CacheMeta {
+            url: url.to_owned(),
+            feed_url: (feed_url != url).then(|| feed_url.to_owned()),
+            etag,
+            last_modified,
+            fetched_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+        fs::write(body_path, body)?;
+        fs::write(
+            meta_path,
+            serde_yaml::to_string(&meta).map_err(io::Error::other)?,
+        )
+    }
+
+    /// Paths of the metadata and body files for `url`
+    fn paths(&self, url: &str) -> (PathBuf, PathBuf) {
+        let key = format!("{:016x}", fnv1a(url.as_bytes()));
+        (
+            self.dir.join(format!("{}.yaml", key)),
+            self.dir.join(format!("{}.body", key)),
+        )
+    }
+}
+
+impl CacheEntry {
+    /// Human readable age of the cached response, e.g. `3h ago`
+    pub fn age(&self) -> String {
+        let secs = self.fetched_at.elapsed().unwrap_or_default().as_secs();
+        match secs {
+            0..60 => "just now".to_string(),
+            60..3600 => format!("{}m ago", secs / 60),
+            3600..86400 => format!("{}h ago", secs / 3600),
+            _ => format!("{}d ago", secs / 86400),
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash, used for stable cache file names
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
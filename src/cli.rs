@@ -0,0 +1,252 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use argh::{FromArgs, SubCommands};
+
+use crate::config::{ChanConfig, ChannelOrder, Dedupe, Order, OutputFormat};
+use crate::query::Query;
+
+/// a RSS channel retriever
+#[derive(Debug, FromArgs)]
+pub struct Cli {
+    /// path to the config file (default: ~/.config/rssget/config.yaml)
+    #[argh(option)]
+    pub config: Option<PathBuf>,
+
+    #[argh(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    /// Parse the command line arguments, exiting on errors or `--help`.
+    /// Arguments without a command are given to `fetch`, so that the flags and
+    /// channels of `rssget [<channels...>] [--display-by <display-by>] ...`
+    /// work as they did before there were commands.
+    pub fn from_env() -> Cli {
+        let strings: Vec<String> = env::args().collect();
+        let cmd = strings
+            .first()
+            .and_then(|arg| Path::new(arg).file_name())
+            .and_then(|name| name.to_str())
+            .unwrap_or("rssget");
+        let mut args: Vec<&str> = strings.iter().skip(1).map(String::as_str).collect();
+        // the command follows any `--config <config>`
+        let mut idx = 0;
+        while args.get(idx) == Some(&"--config") {
+            idx += 2;
+        }
+        if let Some(arg) = args.get(idx)
+            && !matches!(*arg, "help" | "--help")
+            && !Command::COMMANDS.iter().any(|c| c.name == *arg)
+        {
+            args.insert(idx, "fetch");
+        }
+        Cli::from_args(&[cmd], &args).unwrap_or_else(|early_exit| {
+            std::process::exit(match early_exit.status {
+                Ok(()) => {
+                    println!("{}", early_exit.output);
+                    0
+                }
+                Err(()) => {
+                    eprintln!(
+                        "{}\nRun {} --help for more information.",
+                        early_exit.output, cmd
+                    );
+                    1
+                }
+            })
+        })
+    }
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+pub enum Command {
+    Fetch(FetchArgs),
+    Tui(TuiArgs),
+    Add(AddArgs),
+    Remove(RemoveArgs),
+    List(ListArgs),
+    Edit(EditArgs),
+    Validate(ValidateArgs),
+    Import(ImportArgs),
+    Export(ExportArgs),
+}
+
+/// fetch and print RSS items (default command)
+#[derive(Debug, Default, FromArgs)]
+#[argh(subcommand, name = "fetch")]
+pub struct FetchArgs {
+    /// display ordering for RSS items [date | channel | title]
+    #[argh(option)]
+    pub display_by: Option<Order>,
+
+    /// ordering of the channels when displaying by channel [config | name]
+    #[argh(option)]
+    pub channel_order: Option<ChannelOrder>,
+
+    /// reverse the display ordering, e.g. to show the newest items first
+    #[argh(switch)]
+    pub reverse: bool,
+
+    /// output format for RSS items [text | json | ndjson | csv | rss | atom | html]
+    #[argh(option)]
+    pub output: Option<OutputFormat>,
+
+    /// only show items that have not been marked as read
+    #[argh(switch)]
+    pub unread: bool,
+
+    /// mark all displayed items as read
+    #[argh(switch)]
+    pub mark_read: bool,
+
+    /// only read channels from the local cache, without network access
+    #[argh(switch)]
+    pub offline: bool,
+
+    /// max number of items to show across all channels
+    #[argh(option)]
+    pub limit: Option<usize>,
+
+    /// collapse items appearing in several channels [off | links | titles]
+    #[argh(option)]
+    pub dedupe: Option<Dedupe>,
+
+    /// only show items matching this regex in any field, case insensitively
+    #[argh(option)]
+    pub grep: Option<String>,
+
+    /// only show items selected by a query, e.g. 'channel = "HN" and age < 2d'
+    #[argh(option)]
+    pub filter: Option<Query>,
+
+    /// only show items selected by a view from the config file
+    #[argh(option)]
+    pub view: Option<String>,
+
+    /// only fetch the channels of a group from the config file
+    #[argh(option)]
+    pub group: Option<String>,
+
+    /// a list of RSS feed urls, instead of the configured channels
+    #[argh(positional)]
+    pub channels: Vec<ChanConfig>,
+}
+
+/// browse RSS items in an interactive terminal reader
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "tui")]
+pub struct TuiArgs {
+    /// display ordering for RSS items [date | channel | title]
+    #[argh(option)]
+    pub display_by: Option<Order>,
+
+    /// ordering of the channels when displaying by channel [config | name]
+    #[argh(option)]
+    pub channel_order: Option<ChannelOrder>,
+
+    /// reverse the display ordering, e.g. to show the newest items first
+    #[argh(switch)]
+    pub reverse: bool,
+
+    /// only show items that have not been marked as read
+    #[argh(switch)]
+    pub unread: bool,
+
+    /// only read channels from the local cache, without network access
+    #[argh(switch)]
+    pub offline: bool,
+
+    /// only show items selected by a query, e.g. 'channel = "HN" and age < 2d'
+    #[argh(option)]
+    pub filter: Option<Query>,
+
+    /// only show items selected by a view from the config file
+    #[argh(option)]
+    pub view: Option<String>,
+
+    /// only fetch the channels of a group from the config file
+    #[argh(option)]
+    pub group: Option<String>,
+
+    /// a list of RSS feed urls, instead of the configured channels
+    #[argh(positional)]
+    pub channels: Vec<ChanConfig>,
+}
+
+impl From<TuiArgs> for FetchArgs {
+    fn from(args: TuiArgs) -> Self {
+        FetchArgs {
+            display_by: args.display_by,
+            channel_order: args.channel_order,
+            reverse: args.reverse,
+            unread: args.unread,
+            offline: args.offline,
+            filter: args.filter,
+            view: args.view,
+            group: args.group,
+            channels: args.channels,
+            ..Default::default()
+        }
+    }
+}
+
+/// add a channel to the config file
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "add")]
+pub struct AddArgs {
+    /// url of the RSS feed
+    #[argh(positional)]
+    pub url: String,
+
+    /// alias for the channel
+    #[argh(option)]
+    pub alias: Option<String>,
+
+    /// max number of items to show for the channel
+    #[argh(option)]
+    pub max_items: Option<usize>,
+
+    /// group the channel belongs to, may be repeated
+    #[argh(option)]
+    pub group: Vec<String>,
+}
+
+/// remove a channel from the config file
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "remove")]
+pub struct RemoveArgs {
+    /// url or alias of the channel
+    #[argh(positional)]
+    pub channel: String,
+}
+
+/// list the configured channels
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "list")]
+pub struct ListArgs {}
+
+/// open the config file in $VISUAL or $EDITOR
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "edit")]
+pub struct EditArgs {}
+
+/// check the config file for errors
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "validate")]
+pub struct ValidateArgs {}
+
+/// add the channels of an OPML file to the config file
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "import")]
+pub struct ImportArgs {
+    /// path to the OPML file
+    #[argh(positional)]
+    pub file: PathBuf,
+}
+
+/// print the configured channels as OPML
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "export")]
+pub struct ExportArgs {}
@@ -1,50 +1,222 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::str::FromStr;
+use std::time::Duration;
 
-use argh::FromArgs;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-/// a RSS channel retriever
-#[derive(Debug, Deserialize, FromArgs)]
+use crate::cli::FetchArgs;
+use crate::filter::Filter;
+use crate::query::Query;
+
+/// Contents of the config file
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Config {
-    /// display ordering for RSS items [date | channel]
-    #[argh(option, default = "Order::Date")]
+    /// display ordering for RSS items
+    #[serde(default)]
     pub display_by: Order,
 
-    /// a list of RSS feed urls
-    #[argh(positional)]
+    /// ordering of the channel groups when displaying by channel
+    #[serde(default)]
+    pub channel_order: ChannelOrder,
+
+    /// reverse the display ordering, e.g. to show the newest items first
+    #[serde(default)]
+    pub reverse: bool,
+
+    /// output format for RSS items
+    #[serde(default)]
+    pub output: OutputFormat,
+
+    /// only show items that have not been marked as read
+    #[serde(default)]
+    pub unread: bool,
+
+    /// mark all displayed items as read
+    #[serde(default)]
+    pub mark_read: bool,
+
+    /// only read channels from the local cache, without network access
+    #[serde(default)]
+    pub offline: bool,
+
+    /// max number of items to show across all channels
+    #[serde(default)]
+    pub limit: Option<usize>,
+
+    /// hide items older than this, for channels without their own `max_age`
+    #[serde(default, with = "humantime_serde")]
+    pub max_age: Option<Duration>,
+
+    /// how to detect the same item appearing in several channels
+    #[serde(default)]
+    pub dedupe: Dedupe,
+
+    /// rules for including or excluding the items of all channels
+    #[serde(default, skip_serializing_if = "FilterConfig::is_empty")]
+    pub filters: FilterConfig,
+
+    /// only show items matching this regex, given on the command line
+    #[serde(skip)]
+    pub grep: Option<String>,
+
+    /// named queries, selected with `--view`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub views: BTreeMap<String, String>,
+
+    /// only show items selected by this query, given on the command line
+    #[serde(skip)]
+    pub filter: Option<Query>,
+
+    /// name of the view to show, given on the command line
+    #[serde(skip)]
+    pub view: Option<String>,
+
+    /// only fetch the channels of this group, given on the command line
+    #[serde(skip)]
+    pub group: Option<String>,
+
+    /// network settings for all channels
+    #[serde(flatten)]
+    pub http: HttpConfig,
+
+    /// RSS channels to retrieve
+    #[serde(default)]
     pub channels: Vec<ChanConfig>,
 }
 
 impl Config {
+    /// Default location of the config file
+    pub fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .map(|mut d| {
+                d.push("rssget/config.yaml");
+                d
+            })
+            .expect("could not determine system config directory")
+    }
+
+    /// Load the config file at `path`, if present
+    pub fn load(path: &Path) -> Result<Option<Config>, Box<dyn Error>> {
+        match OpenOptions::new().read(true).open(path) {
+            Ok(file) => Ok(Some(serde_yaml::from_reader(&file)?)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(Box::new(err)),
+        }
+    }
+
     /// Validate if this Config is usable
-    pub fn validate(&self) -> Result<(), &str> {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(view) = &self.view
+            && !self.views.contains_key(view)
+        {
+            return Err(format!("No view named {} is configured.", view));
+        }
+        for (name, query) in &self.views {
+            query
+                .parse::<Query>()
+                .map_err(|err| format!("View {}: {}", name, err))?;
+        }
+        if let Some(group) = &self.group
+            && self.channels.is_empty()
+        {
+            return Err(format!("No channels in group {}.", group));
+        }
+        Filter::new(&self.filters)?;
         if self.channels.is_empty() {
-            return Err("No channels configured.");
+            return Err("No channels configured.".to_string());
+        }
+        for (idx, chan) in self.channels.iter().enumerate() {
+            match &chan.command {
+                Some(_) if !chan.url.is_empty() => {
+                    return Err(format!(
+                        "Channel #{} has both a url and a command.",
+                        idx + 1
+                    ));
+                }
+                Some(command) if command.trim().is_empty() => {
+                    return Err(format!("Channel #{} has an empty command.", idx + 1));
+                }
+                None if chan.url.trim().is_empty() => {
+                    return Err(format!("Channel #{} has an empty url.", idx + 1));
+                }
+                _ => {}
+            }
+            if self.channels[..idx]
+                .iter()
+                .any(|c| c.location() == chan.location())
+            {
+                return Err(format!(
+                    "Channel {} is configured more than once.",
+                    chan.location()
+                ));
+            }
+            Filter::new(&chan.filters)
+                .map_err(|err| format!("Channel {}: {}", chan.location(), err))?;
+            if let Some(color) = &chan.color
+                && color.parse::<colored::Color>().is_err()
+            {
+                return Err(format!(
+                    "Channel {} has an unrecognized color {}. [black | red | green | yellow | blue | magenta | cyan | white | bright <color>]",
+                    chan.location(),
+                    color
+                ));
+            }
         }
         Ok(())
     }
 
-    /// Override Self with `other`'s fields
-    pub fn override_with(self, other: Config) -> Config {
-        let channels = if other.channels.is_empty() {
+    /// Override Self with the flags given to the fetch command
+    pub fn override_with(self, args: FetchArgs) -> Config {
+        let mut channels = if args.channels.is_empty() {
             self.channels
         } else {
-            other.channels
+            args.channels
         };
+        if let Some(group) = &args.group {
+            channels.retain(|c| c.in_group(group));
+            // show the channels under the selected group, rather than their first one
+            for chan in &mut channels {
+                chan.groups = vec![group.clone()];
+            }
+        }
         Config {
             channels,
-            display_by: other.display_by,
+            display_by: args.display_by.unwrap_or(self.display_by),
+            channel_order: args.channel_order.unwrap_or(self.channel_order),
+            reverse: self.reverse || args.reverse,
+            output: args.output.unwrap_or(self.output),
+            unread: self.unread || args.unread,
+            mark_read: self.mark_read || args.mark_read,
+            offline: self.offline || args.offline,
+            limit: args.limit.or(self.limit),
+            max_age: self.max_age,
+            dedupe: args.dedupe.unwrap_or(self.dedupe),
+            filters: self.filters,
+            grep: args.grep,
+            views: self.views,
+            filter: args.filter,
+            view: args.view,
+            group: args.group,
+            http: self.http,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Order {
     /// Order by item date
+    #[default]
     Date,
-    /// Order by item's channel
+    /// Group by item's channel, then order by item date
     Channel,
+    /// Order by item title
+    Title,
 }
 
 impl FromStr for Order {
@@ -54,19 +226,151 @@ impl FromStr for Order {
         match s {
             "date" => Ok(Order::Date),
             "channel" => Ok(Order::Channel),
-            _ => Err("Unrecognized Order. [date | channel]".to_string()),
+            "title" => Ok(Order::Title),
+            _ => Err("Unrecognized Order. [date | channel | title]".to_string()),
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelOrder {
+    /// The order the channels are configured in
+    #[default]
+    Config,
+    /// Alphabetical order of the channel names
+    Name,
+}
+
+impl FromStr for ChannelOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "config" => Ok(ChannelOrder::Config),
+            "name" => Ok(ChannelOrder::Name),
+            _ => Err("Unrecognized ChannelOrder. [config | name]".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dedupe {
+    /// Show every item of every channel
+    Off,
+    /// Collapse items with the same id or link
+    #[default]
+    Links,
+    /// Also collapse items with nearly identical titles
+    Titles,
+}
+
+impl FromStr for Dedupe {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Dedupe::Off),
+            "links" => Ok(Dedupe::Links),
+            "titles" => Ok(Dedupe::Titles),
+            _ => Err("Unrecognized Dedupe. [off | links | titles]".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Colored, wrapped text for reading in a terminal
+    #[default]
+    Text,
+    /// A single JSON array
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Comma separated values with a header row
+    Csv,
+    /// A single aggregated RSS 2.0 feed
+    Rss,
+    /// A single aggregated Atom 1.0 feed
+    Atom,
+    /// A self-contained HTML page
+    Html,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "csv" => Ok(OutputFormat::Csv),
+            "rss" => Ok(OutputFormat::Rss),
+            "atom" => Ok(OutputFormat::Atom),
+            "html" => Ok(OutputFormat::Html),
+            _ => Err(
+                "Unrecognized OutputFormat. [text | json | ndjson | csv | rss | atom | html]"
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct ChanConfig {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub url: String,
-    #[serde(default)]
+    /// command whose output is the feed, instead of a url
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub alias: Option<String>,
-    #[serde(default)]
+    /// color of the channel name, e.g. "cyan" or "bright magenta"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// emoji or other short marker shown before the channel name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub max_items: Option<usize>,
+    #[serde(
+        default,
+        with = "humantime_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_age: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub item_config: Option<ItemConfig>,
+    /// groups the channel belongs to, selected with `--group`. Channel ordered
+    /// output shows the channel under the first of them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+    /// rules for including or excluding items, on top of the global ones
+    #[serde(default, skip_serializing_if = "FilterConfig::is_empty")]
+    pub filters: FilterConfig,
+    /// network settings overriding the global ones
+    #[serde(flatten)]
+    pub http: HttpConfig,
+    /// extra HTTP headers sent with each request, e.g. a `Cookie`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, Secret>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+}
+
+impl ChanConfig {
+    /// Url of the channel, or the command producing its feed
+    pub fn location(&self) -> &str {
+        self.command.as_deref().unwrap_or(&self.url)
+    }
+
+    /// Whether the channel belongs to `group`, compared case insensitively
+    pub fn in_group(&self, group: &str) -> bool {
+        self.groups.iter().any(|g| g.eq_ignore_ascii_case(group))
+    }
 }
 
 impl FromStr for ChanConfig {
@@ -75,15 +379,14 @@ impl FromStr for ChanConfig {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(ChanConfig {
             url: s.to_owned(),
-            alias: None,
-            max_items: None,
             item_config: Some(Default::default()),
+            ..Default::default()
         })
     }
 }
 
 /// Toggles for displaying Item fields
-#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
 pub struct ItemConfig {
     #[serde(default)]
     pub hide_title: bool,
@@ -98,3 +401,147 @@ pub struct ItemConfig {
     #[serde(default)]
     pub show_enclosure: bool,
 }
+
+/// Rules for including or excluding items.
+/// Items are kept if they match any include rule, if there are any, and no exclude rule.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct FilterConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<Rule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<Rule>,
+}
+
+impl FilterConfig {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+}
+
+/// A keyword matched case insensitively on any item field, or a keyword or
+/// regex matched on a single field
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Rule {
+    Keyword(String),
+    Match {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        field: Option<ItemField>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        keyword: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        regex: Option<String>,
+    },
+}
+
+/// Item fields that filter rules can match on
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemField {
+    Title,
+    Description,
+    Author,
+    Link,
+    Category,
+}
+
+/// Network settings, given globally and per channel
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct HttpConfig {
+    /// time allowed to connect to the server, e.g. "10s"
+    #[serde(
+        default,
+        with = "humantime_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub connect_timeout: Option<Duration>,
+    /// time allowed between reads of the response, e.g. "30s"
+    #[serde(
+        default,
+        with = "humantime_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub read_timeout: Option<Duration>,
+    /// number of times to retry after a connection error or a 5xx response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    /// delay before the first retry, doubled for every further one, e.g. "1s"
+    #[serde(
+        default,
+        with = "humantime_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub retry_backoff: Option<Duration>,
+    /// value of the `User-Agent` header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// proxy for all requests, e.g. "http://proxy:3128" or "socks5://proxy:1080",
+    /// or "none" to ignore the proxy environment variables
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// PEM file of certificate authorities to trust in addition to the built-in ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<PathBuf>,
+}
+
+impl HttpConfig {
+    /// Override Self with the settings given in `other`
+    pub fn merge(&self, other: &HttpConfig) -> HttpConfig {
+        HttpConfig {
+            connect_timeout: other.connect_timeout.or(self.connect_timeout),
+            read_timeout: other.read_timeout.or(self.read_timeout),
+            retries: other.retries.or(self.retries),
+            retry_backoff: other.retry_backoff.or(self.retry_backoff),
+            user_agent: other.user_agent.clone().or_else(|| self.user_agent.clone()),
+            proxy: other.proxy.clone().or_else(|| self.proxy.clone()),
+            ca_bundle: other.ca_bundle.clone().or_else(|| self.ca_bundle.clone()),
+        }
+    }
+}
+
+/// Credentials sent in the `Authorization` header
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Auth {
+    Basic { username: String, password: Secret },
+    Bearer { token: Secret },
+}
+
+/// A value that is either given in the config file, read from an
+/// environment variable or printed by a command
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Secret {
+    Plain(String),
+    Env { env: String },
+    Command { command: String },
+}
+
+impl Secret {
+    /// Look up the value of this secret
+    pub fn resolve(&self) -> Result<String, String> {
+        match self {
+            Secret::Plain(value) => Ok(value.clone()),
+            Secret::Env { env } => std::env::var(env)
+                .map_err(|err| format!("Could not read secret from ${}: [{}]", env, err)),
+            Secret::Command { command } => {
+                let output = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .output()
+                    .map_err(|err| format!("Could not run `{}`: [{}]", command, err))?;
+                if !output.status.success() {
+                    return Err(format!(
+                        "Could not read secret from `{}`: [{}]",
+                        command, output.status
+                    ));
+                }
+                let value = String::from_utf8(output.stdout).map_err(|err| {
+                    format!("Could not read secret from `{}`: [{}]", command, err)
+                })?;
+                // commands like `pass` end their output with a newline
+                Ok(value.trim_end_matches(['\r', '\n']).to_string())
+            }
+        }
+    }
+}
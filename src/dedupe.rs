@@ -0,0 +1,193 @@
+//! Detection of the same item appearing in several channels
+use std::collections::HashMap;
+
+use url::Url;
+
+use crate::config::Dedupe;
+use crate::item::DisplayItem;
+
+/// Query parameters that only track where a visitor came from
+const TRACKING_PARAMS: [&str; 5] = ["fbclid", "gclid", "mc_cid", "mc_eid", "ref_src"];
+
+/// Collapse items of different channels that are the same story into the
+/// first of them, which lists the others in its `duplicates`.
+/// Items are duplicates if they share a globally unique id, a canonical link,
+/// or with [`Dedupe::Titles`], a title that only differs in case and punctuation.
+pub fn dedupe(items: Vec<DisplayItem>, mode: Dedupe) -> Vec<DisplayItem> {
+    if matches!(mode, Dedupe::Off) {
+        return items;
+    }
+    let mut kept: Vec<DisplayItem> = vec![];
+    // index into `kept` of the item each key was first seen on
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for item in items {
+        let keys = keys(&item, mode);
+        let original = keys
+            .iter()
+            .filter_map(|key| seen.get(key))
+            .find(|idx| kept[**idx].chan_url != item.chan_url)
+            .copied();
+        let idx = match original {
+            Some(idx) => {
+                kept[idx].duplicates.push(item);
+                idx
+            }
+            None => {
+                kept.push(item);
+                kept.len() - 1
+            }
+        };
+        for key in keys {
+            seen.entry(key).or_insert(idx);
+        }
+    }
+    kept
+}
+
+/// Keys identifying the story of `item`, prefixed by their kind
+fn keys(item: &DisplayItem, mode: Dedupe) -> Vec<String> {
+    let mut keys = vec![];
+    if let Some(id) = item.id.as_deref().filter(|id| is_global_id(item, id)) {
+        keys.push(format!("id:{}", id));
+    }
+    if let Some(link) = item.link.as_deref().and_then(canonical_link) {
+        keys.push(format!("link:{}", link));
+    }
+    if matches!(mode, Dedupe::Titles)
+        && let Some(title) = item.title.as_deref().map(normalize_title)
+        && !title.is_empty()
+    {
+        keys.push(format!("title:{}", title));
+    }
+    keys
+}
+
+/// Whether `id` identifies the item across feeds, rather than only within its
+/// own: an absolute url, `urn:` or `tag:` URI that is not just the link or title
+/// standing in for a missing guid
+pub fn is_global_id(item: &DisplayItem, id: &str) -> bool {
+    if item.link.as_deref() == Some(id) || item.title.as_deref() == Some(id) {
+        return false;
+    }
+    Url::parse(id).is_ok_and(|url| url.has_host() || matches!(url.scheme(), "urn" | "tag"))
+}
+
+/// The link without its scheme, a `www.` prefix, fragment, trailing slash or
+/// tracking parameters like `utm_source`
+fn canonical_link(link: &str) -> Option<String> {
+    let mut url = Url::parse(link.trim()).ok()?;
+    url.set_fragment(None);
+    let params: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(name, _)| !name.starts_with("utm_") && !TRACKING_PARAMS.contains(&name.as_ref()))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    if params.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(params);
+    }
+    let host = url.host_str()?;
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let path = url.path().trim_end_matches('/');
+    Some(match url.query() {
+        Some(query) => format!("{}{}?{}", host, path, query),
+        None => format!("{}{}", host, path),
+    })
+}
+
+/// Lowercase the title and reduce it to words, so that titles differing only
+/// in case, punctuation or spacing compare equal
+fn normalize_title(title: &str) -> String {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::{item, titles};
+
+    #[test]
+    fn feed_local_ids_do_not_collide_across_channels() {
+        let items = vec![
+            item("a", "first of a").with_id("1"),
+            item("a", "second of a").with_id("2"),
+            item("b", "first of b").with_id("1"),
+            item("b", "second of b").with_id("2"),
+        ];
+        let items = dedupe(items, Dedupe::Links);
+        assert_eq!(
+            titles(&items),
+            ["first of a", "second of a", "first of b", "second of b"]
+        );
+        assert!(items.iter().all(|i| i.duplicates.is_empty()));
+    }
+
+    #[test]
+    fn global_ids_collide_across_channels() {
+        let items = vec![
+            item("a", "story").with_id("urn:uuid:1234"),
+            item("b", "same story").with_id("urn:uuid:1234"),
+            item("c", "other").with_id("tag:example.com,2024:1"),
+            item("d", "same other").with_id("tag:example.com,2024:1"),
+        ];
+        let items = dedupe(items, Dedupe::Links);
+        assert_eq!(titles(&items), ["story", "other"]);
+        assert_eq!(titles(&items[0].duplicates), ["same story"]);
+    }
+
+    #[test]
+    fn fallback_ids_are_not_keys() {
+        // RSS items without a guid use their title as id
+        let items = vec![
+            item("a", "Weekly news").with_id("Weekly news"),
+            item("b", "Weekly news").with_id("Weekly news"),
+        ];
+        assert_eq!(dedupe(items, Dedupe::Links).len(), 2);
+    }
+
+    #[test]
+    fn links_ignore_tracking_params_www_and_trailing_slash() {
+        let items = vec![
+            item("a", "plain").with_link("https://example.com/post?id=3"),
+            item("b", "tracked")
+                .with_link("http://www.example.com/post/?utm_source=rss&id=3&fbclid=x#top"),
+            item("c", "different").with_link("https://example.com/post?id=4"),
+        ];
+        let items = dedupe(items, Dedupe::Links);
+        assert_eq!(titles(&items), ["plain", "different"]);
+        assert_eq!(titles(&items[0].duplicates), ["tracked"]);
+    }
+
+    #[test]
+    fn same_channel_items_are_kept() {
+        let items = vec![
+            item("a", "first").with_link("https://example.com/1"),
+            item("a", "again").with_link("https://example.com/1"),
+        ];
+        assert_eq!(dedupe(items, Dedupe::Links).len(), 2);
+    }
+
+    #[test]
+    fn titles_mode_matches_normalized_titles() {
+        let items = || {
+            vec![
+                item("a", "Rust 2.0 released!").with_link("https://a.com/1"),
+                item("b", "rust 2.0 -- Released").with_link("https://b.com/9"),
+            ]
+        };
+        assert_eq!(dedupe(items(), Dedupe::Links).len(), 2);
+        let items = dedupe(items(), Dedupe::Titles);
+        assert_eq!(titles(&items), ["Rust 2.0 released!"]);
+        assert_eq!(titles(&items[0].duplicates), ["rust 2.0 -- Released"]);
+        assert_eq!(
+            dedupe(vec![item("a", "x"), item("b", "x")], Dedupe::Off).len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,252 @@
+//! Feed autodiscovery for channel urls that point at a web page
+use url::Url;
+
+/// Feed types advertised by `<link rel="alternate">` tags
+const FEED_TYPES: [&str; 4] = [
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/feed+json",
+    "application/json",
+];
+
+/// Locations that commonly serve a site's feed, relative to the site root
+const COMMON_PATHS: [&str; 6] = [
+    "/feed",
+    "/rss.xml",
+    "/atom.xml",
+    "/feed.xml",
+    "/index.xml",
+    "/rss",
+];
+
+/// Urls that may serve the feed of the web page `html`, loaded from `page_url`.
+/// Feeds linked from the page come first, followed by the common locations.
+pub fn candidates(page_url: &str, html: &str) -> Vec<String> {
+    let Ok(base) = Url::parse(page_url) else {
+        return vec![];
+    };
+    let mut urls: Vec<String> = vec![];
+    let linked = link_tags(html)
+        .into_iter()
+        .filter(|attrs| {
+            let attr = |name| attrs.iter().find(|(n, _)| n == name).map(|(_, v)| v);
+            attr("rel").is_some_and(|rel| {
+                rel.split_ascii_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case("alternate"))
+            }) && attr("type").is_some_and(|t| FEED_TYPES.contains(&t.to_ascii_lowercase().trim()))
+        })
+        .filter_map(|attrs| attrs.into_iter().find(|(n, _)| n == "href"))
+        .filter_map(|(_, href)| base.join(&href).ok());
+    let common = COMMON_PATHS.iter().filter_map(|path| base.join(path).ok());
+    for url in linked.chain(common) {
+        let url = url.to_string();
+        if url != page_url && !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    urls
+}
+
+/// Attributes, as lowercase name and value, of every `<link>` tag in `html`
+fn link_tags(html: &str) -> Vec<Vec<(String, String)>> {
+    let lower = html.to_ascii_lowercase();
+    let mut tags = vec![];
+    let mut rest = 0;
+    while let Some(start) = lower[rest..]
+        .find("<link")
+        .map(|i| rest + i + "<link".len())
+    {
+        let Some(end) = tag_end(&html[start..]).map(|i| start + i) else {
+            break;
+        };
+        rest = end;
+        // `<linkfoo` is some other tag
+        if !html[start..].starts_with(|c: char| c.is_ascii_whitespace()) {
+            continue;
+        }
+        tags.push(attributes(&html[start..end]));
+    }
+    tags
+}
+
+/// Offset of the `>` that closes the tag `tag` is the rest of, skipping any
+/// inside quoted attribute values like `title="a > b"`
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    // whether a quote here would start an attribute value
+    let mut at_value = false;
+    for (i, c) in tag.char_indices() {
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '>' => return Some(i),
+            '"' | '\'' if at_value => quote = Some(c),
+            _ => {}
+        }
+        at_value = c == '=' || (at_value && c.is_ascii_whitespace());
+    }
+    None
+}
+
+/// Parse the attributes of a tag, e.g. ` rel="alternate" href=/feed`
+fn attributes(tag: &str) -> Vec<(String, String)> {
+    let mut attrs = vec![];
+    let mut rest = tag.trim_start();
+    while !rest.is_empty() {
+        let name_len = rest
+            .find(|c: char| c == '=' || c.is_ascii_whitespace() || c == '/')
+            .unwrap_or(rest.len());
+        let name = rest[..name_len].to_ascii_lowercase();
+        rest = rest[name_len..].trim_start();
+        let mut value = String::new();
+        if let Some(after_eq) = rest.strip_prefix('=') {
+            let after_eq = after_eq.trim_start();
+            let (raw, remaining) = match after_eq.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let body = &after_eq[1..];
+                    let len = body.find(quote).unwrap_or(body.len());
+                    (&body[..len], body.get(len + 1..).unwrap_or_default())
+                }
+                _ => {
+                    let len = after_eq
+                        .find(|c: char| c.is_ascii_whitespace())
+                        .unwrap_or(after_eq.len());
+                    (&after_eq[..len], &after_eq[len..])
+                }
+            };
+            value = decode_entities(raw);
+            rest = remaining;
+        } else if name.is_empty() {
+            // skip a stray `/` of a self closing tag
+            rest = &rest[1..];
+        }
+        if !name.is_empty() {
+            attrs.push((name, value));
+        }
+        rest = rest.trim_start();
+    }
+    attrs
+}
+
+/// Decode the character references that commonly appear in attribute values
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMON: [&str; 6] = [
+        "https://example.com/feed",
+        "https://example.com/rss.xml",
+        "https://example.com/atom.xml",
+        "https://example.com/feed.xml",
+        "https://example.com/index.xml",
+        "https://example.com/rss",
+    ];
+
+    /// The candidates other than the common locations
+    fn linked(page_url: &str, html: &str) -> Vec<String> {
+        candidates(page_url, html)
+            .into_iter()
+            .filter(|url| !COMMON.contains(&url.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn quoted_values_may_contain_a_closing_bracket() {
+        let html =
+            r#"<link rel="alternate" title="a > b" type='application/rss+xml' href="/a.xml">"#;
+        assert_eq!(
+            linked("https://example.com/", html),
+            ["https://example.com/a.xml"]
+        );
+        assert_eq!(
+            link_tags(r#"<link title="it's > fine" href=x><link href=y>"#),
+            [
+                vec![
+                    ("title".to_string(), "it's > fine".to_string()),
+                    ("href".to_string(), "x".to_string())
+                ],
+                vec![("href".to_string(), "y".to_string())],
+            ]
+        );
+    }
+
+    #[test]
+    fn unquoted_values_end_at_whitespace() {
+        let html = "<LINK REL=alternate TYPE=application/atom+xml HREF=/atom.php />";
+        assert_eq!(
+            linked("https://example.com/", html),
+            ["https://example.com/atom.php"]
+        );
+        assert_eq!(
+            attributes(" rel=alternate href=/a.xml /"),
+            [
+                ("rel".to_string(), "alternate".to_string()),
+                ("href".to_string(), "/a.xml".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn entities_in_values_are_decoded() {
+        let html = r#"<link rel="alternate" type="application/rss+xml" href="/feed?a=1&amp;b=&quot;2&quot;">"#;
+        assert_eq!(
+            linked("https://example.com/", html),
+            ["https://example.com/feed?a=1&b=%222%22"]
+        );
+    }
+
+    #[test]
+    fn relative_hrefs_are_joined_with_the_page_url() {
+        let html = r#"
+            <link rel="alternate" type="application/rss+xml" href="feed.rss">
+            <link rel="alternate" type="application/feed+json" href="//cdn.example.org/feed.json">
+            <link rel="alternate" type="application/atom+xml" href="https://other.net/atom">
+        "#;
+        assert_eq!(
+            linked("https://example.com/blog/post", html),
+            [
+                "https://example.com/blog/feed.rss",
+                "https://cdn.example.org/feed.json",
+                "https://other.net/atom",
+            ]
+        );
+    }
+
+    #[test]
+    fn only_alternate_feed_links_are_used() {
+        let html = r#"
+            <linkfoo rel="alternate" type="application/rss+xml" href="/foo.xml">
+            <link rel="stylesheet" type="application/rss+xml" href="/style.xml">
+            <link rel="alternate" type="text/html" href="/page.html">
+            <link rel="Alternate home" type=" Application/RSS+XML " href="/b.xml">
+        "#;
+        assert_eq!(
+            linked("https://example.com/", html),
+            ["https://example.com/b.xml"]
+        );
+    }
+
+    #[test]
+    fn common_paths_follow_linked_feeds_without_duplicates() {
+        let html = r#"<link rel="alternate" type="application/rss+xml" href="/rss.xml">"#;
+        let mut expected = vec!["https://example.com/rss.xml"];
+        expected.extend(COMMON.iter().filter(|url| !url.ends_with("/rss.xml")));
+        assert_eq!(candidates("https://example.com/blog", html), expected);
+        // the page itself is not a candidate
+        assert_eq!(candidates("https://example.com/feed", "")[0], COMMON[1]);
+        assert!(candidates("not a url", html).is_empty());
+    }
+}
@@ -0,0 +1,304 @@
+use rss::Channel;
+
+use crate::config::ChanConfig;
+use crate::item::DisplayItem;
+use crate::jsonfeed;
+
+/// Syndication formats that can be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    /// RSS 0.9x, 1.0 (RDF) and 2.0
+    Rss,
+    /// Atom 1.0
+    Atom,
+    /// JSON Feed 1.0 and 1.1
+    Json,
+}
+
+impl FeedFormat {
+    /// Guess the format of a feed document from its root element, or for JSON
+    /// from its JSON Feed `version`
+    pub fn detect(body: &[u8]) -> Option<FeedFormat> {
+        let mut rest = body.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(body);
+        if rest.trim_ascii_start().starts_with(b"{") {
+            return jsonfeed::is_json_feed(rest).then_some(FeedFormat::Json);
+        }
+        // skip the prolog: xml declaration, comments, doctype, etc.
+        loop {
+            rest = rest.trim_ascii_start();
+            let end = if rest.starts_with(b"<!--") {
+                find(rest, b"-->")? + 3
+            } else if rest.starts_with(b"<?") || rest.starts_with(b"<!") {
+                find(rest, b">")? + 1
+            } else {
+                break;
+            };
+            rest = &rest[end..];
+        }
+        let root = rest.strip_prefix(b"<")?;
+        let root = &root[..root
+            .iter()
+            .position(|b| b.is_ascii_whitespace() || *b == b'>' || *b == b'/')
+            .unwrap_or(root.len())];
+        // ignore any namespace prefix, e.g. `rdf:RDF` or `atom:feed`
+        let local_name = root.rsplit(|b| *b == b':').next().unwrap_or(root);
+        match local_name {
+            b"rss" | b"RDF" => Some(FeedFormat::Rss),
+            b"feed" => Some(FeedFormat::Atom),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a feed document of any supported format into DisplayItems of `chan`
+pub fn parse(body: &[u8], chan: &ChanConfig) -> Result<Vec<DisplayItem>, String> {
+    match FeedFormat::detect(body) {
+        Some(FeedFormat::Rss) => match Channel::read_from(body) {
+            Ok(channel) => Ok(channel
+                .items
+                .into_iter()
+                .map(|item| DisplayItem::new(item, &channel.title, chan))
+                .collect()),
+            Err(err) => Err(format!("Could not parse rss response from chan: [{}]", err)),
+        },
+        Some(FeedFormat::Atom) => match atom_syndication::Feed::read_from(body) {
+            Ok(feed) => Ok(feed
+                .entries
+                .into_iter()
+                .map(|entry| DisplayItem::from_atom(entry, &feed.title.value, chan))
+                .collect()),
+            Err(err) => Err(format!(
+                "Could not parse atom response from chan: [{}]",
+                err
+            )),
+        },
+        Some(FeedFormat::Json) => match serde_json::from_slice::<jsonfeed::Feed>(body) {
+            Ok(feed) => Ok(feed
+                .items
+                .into_iter()
+                .map(|item| DisplayItem::from_json_feed(item, &feed.title, chan))
+                .collect()),
+            Err(err) => Err(format!(
+                "Could not parse json feed response from chan: [{}]",
+                err
+            )),
+        },
+        None => Err("Could not parse response from chan: [unrecognized feed format]".to_string()),
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chan() -> ChanConfig {
+        "https://example.com/feed".parse().unwrap()
+    }
+
+    #[test]
+    fn detect_reads_the_root_element() {
+        let detect = |body: &str| FeedFormat::detect(body.as_bytes());
+        assert_eq!(detect("<rss version=\"2.0\"></rss>"), Some(FeedFormat::Rss));
+        assert_eq!(
+            detect("\u{feff}<?xml version=\"1.0\"?>\n<rss/>"),
+            Some(FeedFormat::Rss)
+        );
+        assert_eq!(
+            detect("<?xml version=\"1.0\"?><!-- a <feed> comment --><!DOCTYPE rss>\n<rss>"),
+            Some(FeedFormat::Rss)
+        );
+        assert_eq!(
+            detect("<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">"),
+            Some(FeedFormat::Rss)
+        );
+        assert_eq!(
+            detect("<feed xmlns=\"http://www.w3.org/2005/Atom\">"),
+            Some(FeedFormat::Atom)
+        );
+        assert_eq!(
+            detect("  <atom:feed xmlns:atom=\"http://www.w3.org/2005/Atom\">"),
+            Some(FeedFormat::Atom)
+        );
+        assert_eq!(
+            detect(r#"{"version": "https://jsonfeed.org/version/1.1", "title": "a"}"#),
+            Some(FeedFormat::Json)
+        );
+    }
+
+    #[test]
+    fn detect_rejects_other_documents() {
+        let detect = |body: &str| FeedFormat::detect(body.as_bytes());
+        assert_eq!(detect("<!DOCTYPE html>\n<html><head></head></html>"), None);
+        assert_eq!(detect("<feedback/>"), None);
+        assert_eq!(detect(r#"{"version": "1.0", "name": "api"}"#), None);
+        assert_eq!(detect("<!-- unterminated comment <rss>"), None);
+        assert_eq!(detect(""), None);
+    }
+
+    #[test]
+    fn atom_entries_map_to_display_items() {
+        let body = br#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Blog</title>
+  <id>urn:uuid:feed</id>
+  <updated>2024-01-05T00:00:00Z</updated>
+  <entry>
+    <title>First</title>
+    <id>urn:uuid:1</id>
+    <link rel="related" href="https://example.com/related"/>
+    <link rel="enclosure" href="https://example.com/1.mp3"/>
+    <link rel="alternate" href="https://example.com/posts/1"/>
+    <published>2024-01-02T03:04:05+02:00</published>
+    <updated>2024-01-04T00:00:00Z</updated>
+    <author><name>Ann</name></author>
+    <author><name>Bob</name></author>
+    <category term="rust" label="Rust"/>
+    <category term="news"/>
+    <summary type="text">Use Vec&lt;String&gt;</summary>
+    <content type="html">&lt;p&gt;content&lt;/p&gt;</content>
+  </entry>
+  <entry>
+    <title>Second</title>
+    <id>urn:uuid:2</id>
+    <link href="https://example.com/posts/2"/>
+    <updated>2024-01-03T00:00:00Z</updated>
+    <summary type="html">&lt;p&gt;summary&lt;/p&gt;</summary>
+  </entry>
+  <entry>
+    <title>Third</title>
+    <id>urn:uuid:3</id>
+    <link rel="self" href="https://example.com/posts/3.atom"/>
+    <updated>2024-01-01T00:00:00Z</updated>
+    <content type="text">plain content</content>
+  </entry>
+  <entry>
+    <title>Fourth</title>
+    <id>urn:uuid:4</id>
+    <updated>2024-01-01T00:00:00Z</updated>
+    <content type="xhtml"><div xmlns="http://www.w3.org/1999/xhtml"><p>xhtml</p></div></content>
+  </entry>
+</feed>"#;
+        let items = parse(body, &chan()).unwrap();
+        assert_eq!(items.len(), 4);
+
+        let first = &items[0];
+        assert_eq!(first.chan_title, "Blog");
+        assert_eq!(first.id.as_deref(), Some("urn:uuid:1"));
+        assert_eq!(first.title.as_deref(), Some("First"));
+        assert_eq!(first.link.as_deref(), Some("https://example.com/posts/1"));
+        assert_eq!(
+            first.enclosure_url.as_deref(),
+            Some("https://example.com/1.mp3")
+        );
+        // the summary is preferred over the content
+        assert_eq!(first.description.as_deref(), Some("Use Vec<String>"));
+        assert!(!first.description_is_html);
+        assert_eq!(
+            first.pub_date.map(|d| d.to_rfc3339()).as_deref(),
+            Some("2024-01-02T03:04:05+02:00")
+        );
+        assert_eq!(first.author.as_deref(), Some("Ann"));
+        assert_eq!(first.categories, ["Rust", "news"]);
+
+        // without a published date the updated one is used
+        let second = &items[1];
+        assert_eq!(second.link.as_deref(), Some("https://example.com/posts/2"));
+        assert_eq!(second.enclosure_url, None);
+        assert_eq!(second.description.as_deref(), Some("<p>summary</p>"));
+        assert!(second.description_is_html);
+        assert_eq!(
+            second.pub_date.map(|d| d.to_rfc3339()).as_deref(),
+            Some("2024-01-03T00:00:00+00:00")
+        );
+
+        // without an alternate link the first one is used
+        let third = &items[2];
+        assert_eq!(
+            third.link.as_deref(),
+            Some("https://example.com/posts/3.atom")
+        );
+        assert_eq!(third.description.as_deref(), Some("plain content"));
+        assert!(!third.description_is_html);
+
+        let fourth = &items[3];
+        assert_eq!(fourth.link, None);
+        assert!(fourth
+            .description
+            .as_deref()
+            .is_some_and(|d| d.contains("<p>xhtml</p>")));
+        assert!(fourth.description_is_html);
+    }
+
+    #[test]
+    fn json_feed_items_map_to_display_items() {
+        let body = br#"{
+            "version": "https://jsonfeed.org/version/1.1",
+            "title": "Blog",
+            "items": [
+                {
+                    "id": "https://example.com/1",
+                    "url": "https://example.com/posts/1",
+                    "title": "First",
+                    "content_text": "text",
+                    "content_html": "<p>html</p>",
+                    "date_published": "2024-01-02T03:04:05+02:00",
+                    "authors": [{"url": "https://example.com/ann"}, {"name": "Ann"}],
+                    "author": {"name": "Old Ann"},
+                    "tags": ["rust", "news"],
+                    "attachments": [{"url": "https://example.com/1.mp3", "mime_type": "audio/mpeg"}]
+                },
+                {"id": 2, "summary": "summary", "content_html": "<p>html</p>"},
+                {"id": 3.5, "content_html": "<p>html</p>", "author": {"name": "Bob"}}
+            ]
+        }"#;
+        let items = parse(body, &chan()).unwrap();
+        assert_eq!(items.len(), 3);
+
+        let first = &items[0];
+        assert_eq!(first.chan_title, "Blog");
+        assert_eq!(first.id.as_deref(), Some("https://example.com/1"));
+        assert_eq!(first.link.as_deref(), Some("https://example.com/posts/1"));
+        assert_eq!(first.title.as_deref(), Some("First"));
+        assert_eq!(first.description.as_deref(), Some("text"));
+        assert!(!first.description_is_html);
+        assert_eq!(
+            first.pub_date.map(|d| d.to_rfc3339()).as_deref(),
+            Some("2024-01-02T03:04:05+02:00")
+        );
+        assert_eq!(first.author.as_deref(), Some("Ann"));
+        assert_eq!(first.categories, ["rust", "news"]);
+        assert_eq!(
+            first.enclosure_url.as_deref(),
+            Some("https://example.com/1.mp3")
+        );
+
+        // summaries are plain text too, and only `content_html` is HTML
+        assert_eq!(items[1].id.as_deref(), Some("2"));
+        assert_eq!(items[1].description.as_deref(), Some("summary"));
+        assert!(!items[1].description_is_html);
+        assert_eq!(items[2].id.as_deref(), Some("3.5"));
+        assert_eq!(items[2].description.as_deref(), Some("<p>html</p>"));
+        assert!(items[2].description_is_html);
+        assert_eq!(items[2].author.as_deref(), Some("Bob"));
+    }
+
+    #[test]
+    fn json_feed_items_with_bad_dates_are_undated() {
+        let body = br#"{
+            "version": "https://jsonfeed.org/version/1",
+            "title": "Blog",
+            "items": [
+                {"id": "1", "title": "date only", "date_published": "2024-01-04"},
+                {"id": "2", "title": "garbage", "date_published": "yesterday"},
+                {"id": "3", "title": "dated", "date_published": "2024-01-04T00:00:00Z"}
+            ]
+        }"#;
+        let items = parse(body, &chan()).unwrap();
+        let dates: Vec<_> = items.iter().map(|i| i.pub_date.is_some()).collect();
+        assert_eq!(dates, [false, false, true]);
+    }
+}
@@ -0,0 +1,642 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rustls::{ClientConfig, RootCertStore};
+use ureq::{Agent, AgentBuilder, ErrorKind, Proxy};
+use url::{Origin, Url};
+
+use crate::cache::{CacheEntry, FeedCache};
+use crate::config::{Auth, ChanConfig, HttpConfig};
+use crate::discover;
+use crate::feed::FeedFormat;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+const RETRIES: u32 = 2;
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+const USER_AGENT: &str = concat!("rssget/", env!("CARGO_PKG_VERSION"));
+const MAX_REDIRECTS: u32 = 5;
+
+/// An HTTP agent along with the retry policy and extra headers for its requests
+pub struct Client {
+    agent: Agent,
+    retries: u32,
+    retry_backoff: Duration,
+    headers: Vec<(String, String)>,
+    /// Origin of the channel, the only one `headers` are sent to
+    origin: Option<Origin>,
+}
+
+impl Client {
+    /// Build a client for requests to `url`, using defaults for the settings not given
+    pub fn new(settings: &HttpConfig, url: &str) -> Result<Client, String> {
+        // redirects are followed by `attempt`, to only send the channel's headers to its origin
+        let mut builder = AgentBuilder::new()
+            .redirects(0)
+            .timeout_connect(settings.connect_timeout.unwrap_or(CONNECT_TIMEOUT))
+            .timeout_read(settings.read_timeout.unwrap_or(READ_TIMEOUT))
+            .user_agent(settings.user_agent.as_deref().unwrap_or(USER_AGENT));
+        if let Some(proxy) = proxy_for(url, settings.proxy.as_deref()) {
+            let proxy =
+                Proxy::new(&proxy).map_err(|err| format!("Invalid proxy {}: [{}]", proxy, err))?;
+            builder = builder.proxy(proxy);
+        }
+        if let Some(ca_bundle) = &settings.ca_bundle {
+            builder = builder.tls_config(tls_config(ca_bundle)?);
+        }
+        Ok(Client {
+            agent: builder.build(),
+            retries: settings.retries.unwrap_or(RETRIES),
+            retry_backoff: settings.retry_backoff.unwrap_or(RETRY_BACKOFF),
+            headers: vec![],
+            origin: None,
+        })
+    }
+
+    /// Build a client for `chan`, with the global network settings overridden
+    /// by those of the channel, and its headers and credentials. These are only
+    /// sent to the channel's own origin, not to feeds discovered on other hosts.
+    pub fn for_channel(settings: &HttpConfig, chan: &ChanConfig) -> Result<Client, String> {
+        let mut client = Client::new(&settings.merge(&chan.http), &chan.url)?;
+        client.origin = Url::parse(&chan.url).ok().map(|url| url.origin());
+        for (name, value) in &chan.headers {
+            client.headers.push((name.clone(), value.resolve()?));
+        }
+        let authorization = match &chan.auth {
+            Some(Auth::Basic { username, password }) => Some(format!(
+                "Basic {}",
+                BASE64.encode(format!("{}:{}", username, password.resolve()?))
+            )),
+            Some(Auth::Bearer { token }) => Some(format!("Bearer {}", token.resolve()?)),
+            None => None,
+        };
+        if let Some(authorization) = authorization {
+            client
+                .headers
+                .push(("Authorization".to_string(), authorization));
+        }
+        Ok(client)
+    }
+
+    fn without_retries(&self) -> Client {
+        Client {
+            agent: self.agent.clone(),
+            retries: 0,
+            retry_backoff: self.retry_backoff,
+            headers: self.headers.clone(),
+            origin: self.origin.clone(),
+        }
+    }
+}
+
+/// Whether `url` has the same scheme, host and port as `origin`
+fn same_origin(url: &str, origin: &Origin) -> bool {
+    Url::parse(url).is_ok_and(|url| url.origin() == *origin)
+}
+
+/// The proxy to use for `url`. Without a configured one, the usual
+/// `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY` and `NO_PROXY` variables apply.
+fn proxy_for(url: &str, configured: Option<&str>) -> Option<String> {
+    match configured {
+        Some("none") => return None,
+        Some(proxy) => return Some(proxy.to_owned()),
+        None => {}
+    }
+    let url = Url::parse(url).ok()?;
+    let proxy = match url.scheme() {
+        "https" => env_var(&["HTTPS_PROXY", "https_proxy"]),
+        "http" => env_var(&["HTTP_PROXY", "http_proxy"]),
+        _ => None,
+    }
+    .or_else(|| env_var(&["ALL_PROXY", "all_proxy"]))?;
+
+    let host = url.host_str()?;
+    let bypass =
+        env_var(&["NO_PROXY", "no_proxy"]).is_some_and(|no_proxy| bypasses_proxy(host, &no_proxy));
+    (!bypass).then_some(proxy)
+}
+
+/// Whether `host` matches an entry of the `NO_PROXY` list `no_proxy`
+fn bypasses_proxy(host: &str, no_proxy: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    no_proxy.split(',').map(str::trim).any(|pattern| {
+        // entries may come with a leading dot, e.g. `.example.com`
+        let domain = no_proxy_host(pattern).trim_start_matches('.');
+        pattern == "*"
+            || (!domain.is_empty() && (host == domain || host.ends_with(&format!(".{}", domain))))
+    })
+}
+
+/// The host of a `NO_PROXY` entry without its port or IPv6 brackets,
+/// e.g. `example.com` for `example.com:8080` and `::1` for `[::1]:8080`
+fn no_proxy_host(pattern: &str) -> &str {
+    if let Some(rest) = pattern.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or_default();
+    }
+    match pattern.rsplit_once(':') {
+        // a bare IPv6 address like `::1` has no port
+        Some((host, port)) if !host.contains(':') && port.bytes().all(|b| b.is_ascii_digit()) => {
+            host
+        }
+        _ => pattern,
+    }
+}
+
+/// The value of the first of `names` that is set and not empty
+fn env_var(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find_map(|name| env::var(name).ok().filter(|v| !v.is_empty()))
+}
+
+/// TLS settings trusting the certificates in `ca_bundle` along with the built-in ones
+fn tls_config(ca_bundle: &Path) -> Result<Arc<ClientConfig>, String> {
+    let err = |err: &dyn std::fmt::Display| {
+        format!(
+            "Could not read CA bundle {}: [{}]",
+            ca_bundle.display(),
+            err
+        )
+    };
+    let mut roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let file = File::open(ca_bundle).map_err(|e| err(&e))?;
+    for cert in rustls_pemfile::certs(&mut BufReader::new(file)) {
+        roots.add(cert.map_err(|e| err(&e))?).map_err(|e| err(&e))?;
+    }
+    let config = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(Arc::new(config))
+}
+
+/// A downloaded document along with its cache validators
+struct Response {
+    body: Vec<u8>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// The result of a single successful request.
+/// `body` is `None` if the cached copy is still up to date.
+struct Reply {
+    body: Option<Vec<u8>>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// A failed request, and whether trying again may succeed
+struct Failure {
+    message: String,
+    transient: bool,
+}
+
+/// Download the feed document of `chan`.
+/// Sends the validators of a cached copy, if any, and reuses the cached
+/// body when the server answers `304 Not Modified`. If the channel url
+/// points at a web page instead of a feed, the feed it links to is used.
+pub fn fetch(client: &Client, cache: &FeedCache, chan: &ChanConfig) -> Result<Vec<u8>, String> {
+    let cached = cache.get(&chan.url);
+    // skip discovery if the feed of a web page was found before
+    let mut feed_url = cached
+        .as_ref()
+        .map_or_else(|| chan.url.clone(), |c| c.feed_url.clone());
+    let mut res = get(client, &feed_url, cached);
+    if feed_url != chan.url
+        && !res
+            .as_ref()
+            .is_ok_and(|res| FeedFormat::detect(&res.body).is_some())
+    {
+        // the feed found before is gone, look for it on the page again
+        feed_url = chan.url.clone();
+        res = get(client, &feed_url, None);
+    }
+    let mut res = res?;
+    if FeedFormat::detect(&res.body).is_none() {
+        (feed_url, res) = discover_feed(client, &feed_url, &res.body)?;
+    }
+    // a failed cache write only costs a full download next time
+    let _ = cache.put(&chan.url, &feed_url, res.etag, res.last_modified, &res.body);
+    Ok(res.body)
+}
+
+/// Find the url of the feed at `url`, which is either a feed itself or a
+/// web page linking to one. Returns `None` if the page has no feed.
+pub fn resolve_feed_url(client: &Client, url: &str) -> Result<Option<String>, String> {
+    let res = get(client, url, None)?;
+    if FeedFormat::detect(&res.body).is_some() {
+        return Ok(Some(url.to_owned()));
+    }
+    Ok(discover_feed(client, url, &res.body)
+        .ok()
+        .map(|(feed_url, _)| feed_url))
+}
+
+/// Whether `url` is read from a `file://` url or stdin (`-`) rather than over HTTP
+pub fn is_local(url: &str) -> bool {
+    url == "-" || url.starts_with("file://")
+}
+
+/// Read the feed document of `chan` if it is produced by a command, or comes
+/// from a `file://` url or stdin (`-`). Returns `None` for channels that are
+/// fetched over HTTP.
+pub fn read_local(chan: &ChanConfig) -> Option<Result<Vec<u8>, String>> {
+    if let Some(command) = &chan.command {
+        return Some(run_command(command));
+    }
+    if !is_local(&chan.url) {
+        return None;
+    }
+    if chan.url == "-" {
+        let mut body = vec![];
+        return Some(
+            io::stdin()
+                .lock()
+                .read_to_end(&mut body)
+                .map(|_| body)
+                .map_err(|err| format!("Could not read rss from stdin: [{}]", err)),
+        );
+    }
+    let path = chan.url.strip_prefix("file://")?;
+    // fall back to the plain path for relative ones like `file://feeds/news.xml`
+    let path = Url::parse(&chan.url)
+        .ok()
+        .and_then(|url| url.to_file_path().ok())
+        .unwrap_or_else(|| PathBuf::from(path));
+    Some(
+        std::fs::read(&path)
+            .map_err(|err| format!("Could not read rss file: [{}: {}]", path.display(), err)),
+    )
+}
+
+/// Run `command` with the shell and return its output
+fn run_command(command: &str) -> Result<Vec<u8>, String> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| format!("Could not run rss command: [{}: {}]", command, err))?;
+    if !output.status.success() {
+        // the last line of stderr usually says what went wrong
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr
+            .lines()
+            .rev()
+            .find(|l| !l.trim().is_empty())
+            .map_or_else(|| output.status.to_string(), str::to_owned);
+        return Err(format!("Rss command failed: [{}: {}]", command, reason));
+    }
+    Ok(output.stdout)
+}
+
+/// Load the last cached feed document of `chan` without any network access.
+/// Returns the document along with the age of the cached copy.
+/// Command channels are unavailable, since their commands may access the network.
+pub fn fetch_cached(cache: &FeedCache, chan: &ChanConfig) -> Result<(Vec<u8>, String), String> {
+    if let Some(command) = &chan.command {
+        return Err(format!("Rss command is not run offline: [{}]", command));
+    }
+    cache
+        .get(&chan.url)
+        .map(|cached| {
+            let age = cached.age();
+            (cached.body, age)
+        })
+        .ok_or_else(|| format!("No cached copy of rss: [{}]", chan.url))
+}
+
+/// Download `url`, revalidating `cached` if it was downloaded from the same url.
+/// Connection errors and 5xx responses are retried with exponential backoff.
+fn get(client: &Client, url: &str, cached: Option<CacheEntry>) -> Result<Response, String> {
+    let cached = cached.filter(|c| c.feed_url == url);
+    let mut failures = vec![];
+    for retry in 0..=client.retries {
+        if retry > 0 {
+            thread::sleep(client.retry_backoff * 2u32.saturating_pow(retry - 1));
+        }
+        match attempt(client, url, cached.as_ref()) {
+            Ok(reply) => {
+                return Ok(match (reply.body, cached) {
+                    (None, Some(cached)) => Response {
+                        body: cached.body,
+                        // a 304 response may omit the validators, keep the cached ones
+                        etag: reply.etag.or(cached.etag),
+                        last_modified: reply.last_modified.or(cached.last_modified),
+                    },
+                    (body, _) => Response {
+                        body: body.unwrap_or_default(),
+                        etag: reply.etag,
+                        last_modified: reply.last_modified,
+                    },
+                });
+            }
+            Err(failure) => {
+                let transient = failure.transient;
+                failures.push(failure.message);
+                if !transient {
+                    break;
+                }
+            }
+        }
+    }
+    Err(match failures.as_slice() {
+        [failure] => failure.clone(),
+        _ => format!(
+            "Failed {} attempts to fetch rss: [{}]\n  {}",
+            failures.len(),
+            url,
+            failures.join("\n  ")
+        ),
+    })
+}
+
+/// Make a single request for `url`, with the validators of `cached` if any.
+/// Redirects are followed, sending the channel's headers only to hops on its origin.
+fn attempt(client: &Client, url: &str, cached: Option<&CacheEntry>) -> Result<Reply, Failure> {
+    let mut url = url.to_owned();
+    let mut redirects = 0;
+    let res = loop {
+        let res = request(client, &url, cached)?;
+        let location = match res.header("Location") {
+            Some(location) if (300..400).contains(&res.status()) && res.status() != 304 => location,
+            _ => break res,
+        };
+        if redirects == MAX_REDIRECTS {
+            return Err(Failure {
+                transient: false,
+                message: format!("Too many redirects fetching rss: [{}]", url),
+            });
+        }
+        url = Url::parse(&url)
+            .and_then(|base| base.join(location))
+            .map_err(|err| Failure {
+                transient: false,
+                message: format!("Bad redirect for rss: [{}: {}]", location, err),
+            })?
+            .into();
+        redirects += 1;
+    };
+    let etag = res.header("ETag").map(str::to_owned);
+    let last_modified = res.header("Last-Modified").map(str::to_owned);
+    if cached.is_some() && res.status() == 304 {
+        return Ok(Reply {
+            body: None,
+            etag,
+            last_modified,
+        });
+    }
+
+    let mut body = vec![];
+    res.into_reader()
+        .read_to_end(&mut body)
+        .map_err(|err| Failure {
+            transient: true,
+            message: format!("Could not read rss response: [{}]", err),
+        })?;
+    Ok(Reply {
+        body: Some(body),
+        etag,
+        last_modified,
+    })
+}
+
+/// Send a single request for `url` without following redirects
+fn request(
+    client: &Client,
+    url: &str,
+    cached: Option<&CacheEntry>,
+) -> Result<ureq::Response, Failure> {
+    let mut request = client.agent.get(url);
+    if client
+        .origin
+        .as_ref()
+        .is_some_and(|origin| same_origin(url, origin))
+    {
+        for (name, value) in &client.headers {
+            request = request.set(name, value);
+        }
+    }
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            request = request.set("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.set("If-Modified-Since", last_modified);
+        }
+    }
+    request.call().map_err(|err| Failure {
+        transient: match &err {
+            ureq::Error::Status(status, _) => *status >= 500,
+            // errors like a bad url or proxy would fail the same way again
+            ureq::Error::Transport(transport) => matches!(
+                transport.kind(),
+                ErrorKind::Dns | ErrorKind::ConnectionFailed | ErrorKind::Io
+            ),
+        },
+        message: format!("Could not reach rss: [{}]", err),
+    })
+}
+
+/// Look for a feed linked from the web page `page_url`, or at one of the
+/// common feed locations of its site
+fn discover_feed(
+    client: &Client,
+    page_url: &str,
+    page: &[u8],
+) -> Result<(String, Response), String> {
+    let html = String::from_utf8_lossy(page);
+    // most candidates don't exist, so don't wait for retries on each of them
+    let client = client.without_retries();
+    for candidate in discover::candidates(page_url, &html) {
+        if let Ok(res) = get(&client, &candidate, None)
+            && FeedFormat::detect(&res.body).is_some()
+        {
+            return Ok((candidate, res));
+        }
+    }
+    Err(format!("Could not find a feed at: [{}]", page_url))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::sync::mpsc::{self, Receiver};
+
+    use super::*;
+    use crate::config::Secret;
+
+    /// Answer one connection on `listener` with each of `responses`,
+    /// passing on the requests received
+    fn serve(listener: TcpListener, responses: Vec<String>) -> Receiver<String> {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = vec![];
+                let mut byte = [0];
+                while !request.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+                    request.push(byte[0]);
+                }
+                tx.send(String::from_utf8_lossy(&request).into_owned())
+                    .unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        rx
+    }
+
+    fn ok(body: &str) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    fn redirect(location: &str) -> String {
+        format!(
+            "HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            location
+        )
+    }
+
+    #[test]
+    fn same_origin_compares_scheme_host_and_port() {
+        let origin = Url::parse("https://example.com/feed.xml").unwrap().origin();
+        assert!(same_origin("https://example.com/other/rss", &origin));
+        assert!(same_origin("https://example.com:443/rss", &origin));
+        assert!(!same_origin("http://example.com/rss", &origin));
+        assert!(!same_origin("https://example.com:8443/rss", &origin));
+        assert!(!same_origin("https://feeds.example.com/rss", &origin));
+        assert!(!same_origin("https://example.com.evil.net/rss", &origin));
+        assert!(!same_origin("not a url", &origin));
+    }
+
+    #[test]
+    fn no_proxy_entries_match_hosts_and_their_subdomains() {
+        assert!(bypasses_proxy("example.com", "*"));
+        assert!(bypasses_proxy("example.com", "other.net, example.com"));
+        assert!(bypasses_proxy("example.com", ".example.com"));
+        assert!(bypasses_proxy("feeds.example.com", ".example.com"));
+        assert!(bypasses_proxy("feeds.example.com", "example.com"));
+        assert!(!bypasses_proxy("badexample.com", "example.com"));
+        assert!(!bypasses_proxy("example.com", "feeds.example.com"));
+        assert!(!bypasses_proxy("example.com", ""));
+    }
+
+    #[test]
+    fn no_proxy_entries_may_have_a_port() {
+        assert!(bypasses_proxy("example.com", "example.com:8080"));
+        assert!(bypasses_proxy("feeds.example.com", ".example.com:443"));
+        assert!(bypasses_proxy("10.0.0.1", "10.0.0.1:3128"));
+        assert!(bypasses_proxy("[::1]", "::1"));
+        assert!(bypasses_proxy("[::1]", "[::1]:8080"));
+        assert!(bypasses_proxy("[::1]", "[::1]"));
+        assert!(!bypasses_proxy("[::2]", "::1"));
+    }
+
+    #[test]
+    fn configured_proxy_overrides_the_environment() {
+        assert_eq!(proxy_for("https://example.com/rss", Some("none")), None);
+        assert_eq!(
+            proxy_for("https://example.com/rss", Some("socks5://localhost:1080")).as_deref(),
+            Some("socks5://localhost:1080")
+        );
+    }
+
+    #[test]
+    fn redirects_only_carry_headers_within_the_origin() {
+        let channel_host = TcpListener::bind("127.0.0.1:0").unwrap();
+        let other_host = TcpListener::bind("127.0.0.1:0").unwrap();
+        let channel_url = format!("http://{}", channel_host.local_addr().unwrap());
+        let other_url = format!("http://{}", other_host.local_addr().unwrap());
+        let channel_requests = serve(
+            channel_host,
+            vec![redirect("/feed/"), redirect(&format!("{}/rss", other_url))],
+        );
+        let body = "<rss version=\"2.0\"><channel></channel></rss>";
+        let other_requests = serve(other_host, vec![ok(body)]);
+
+        let mut chan: ChanConfig = format!("{}/feed", channel_url).parse().unwrap();
+        chan.headers
+            .insert("X-Api-Key".to_owned(), Secret::Plain("key".to_owned()));
+        chan.auth = Some(Auth::Bearer {
+            token: Secret::Plain("token".to_owned()),
+        });
+        let settings = HttpConfig {
+            proxy: Some("none".to_owned()),
+            retries: Some(0),
+            ..Default::default()
+        };
+        let client = Client::for_channel(&settings, &chan).unwrap();
+        let res = get(&client, &chan.url, None).unwrap();
+        assert_eq!(res.body, body.as_bytes());
+
+        for path in ["/feed ", "/feed/ "] {
+            let request = channel_requests.recv().unwrap().to_lowercase();
+            assert!(request.starts_with(&format!("get {}", path)), "{}", request);
+            assert!(request.contains("x-api-key: key"), "{}", request);
+            assert!(
+                request.contains("authorization: bearer token"),
+                "{}",
+                request
+            );
+        }
+        let request = other_requests.recv().unwrap().to_lowercase();
+        assert!(request.starts_with("get /rss "), "{}", request);
+        assert!(!request.contains("x-api-key"), "{}", request);
+        assert!(!request.contains("authorization"), "{}", request);
+    }
+
+    #[test]
+    fn fetch_rediscovers_a_feed_that_is_gone() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let site = format!("http://{}", listener.local_addr().unwrap());
+        let body = "<rss version=\"2.0\"><channel></channel></rss>";
+        let page = r#"<html><head><link rel="alternate" type="application/rss+xml" href="/new.xml"></head></html>"#;
+        let requests = serve(
+            listener,
+            vec![
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_owned(),
+                ok(page),
+                ok(body),
+            ],
+        );
+
+        let dir = env::temp_dir().join(format!("rssget-test-{}", std::process::id()));
+        let cache = FeedCache::in_dir(dir.clone());
+        let chan: ChanConfig = format!("{}/", site).parse().unwrap();
+        let old_feed = format!("{}/old.xml", site);
+        cache
+            .put(&chan.url, &old_feed, None, None, body.as_bytes())
+            .unwrap();
+        let settings = HttpConfig {
+            proxy: Some("none".to_owned()),
+            retries: Some(0),
+            ..Default::default()
+        };
+        let client = Client::for_channel(&settings, &chan).unwrap();
+        let fetched = fetch(&client, &cache, &chan);
+        let cached = cache.get(&chan.url);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(fetched.unwrap(), body.as_bytes());
+        assert_eq!(cached.unwrap().feed_url, format!("{}/new.xml", site));
+        for path in ["/old.xml ", "/ ", "/new.xml "] {
+            let request = requests.recv().unwrap();
+            assert!(request.starts_with(&format!("GET {}", path)), "{}", request);
+        }
+    }
+}
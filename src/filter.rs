@@ -0,0 +1,192 @@
+//! Keyword and regex rules for including or excluding items
+use regex::{Regex, RegexBuilder};
+
+use crate::config::{FilterConfig, ItemField, Rule};
+use crate::item::DisplayItem;
+
+/// Compiled include and exclude rules
+#[derive(Debug, Default)]
+pub struct Filter {
+    include: Vec<Matcher>,
+    exclude: Vec<Matcher>,
+}
+
+#[derive(Debug)]
+struct Matcher {
+    /// Field to match on, or any of them if `None`
+    field: Option<ItemField>,
+    pattern: Pattern,
+}
+
+#[derive(Debug)]
+enum Pattern {
+    /// Lowercase keyword, matched case insensitively
+    Keyword(String),
+    Regex(Regex),
+}
+
+impl Filter {
+    /// Compile the rules of `config`
+    pub fn new(config: &FilterConfig) -> Result<Filter, String> {
+        let compile = |rules: &[Rule]| rules.iter().map(Matcher::new).try_collect();
+        Ok(Filter {
+            include: compile(&config.include)?,
+            exclude: compile(&config.exclude)?,
+        })
+    }
+
+    /// A filter only keeping items that match `pattern` in any field, case insensitively
+    pub fn grep(pattern: &str) -> Result<Filter, String> {
+        Ok(Filter {
+            include: vec![Matcher {
+                field: None,
+                pattern: Pattern::Regex(regex(pattern, true)?),
+            }],
+            exclude: vec![],
+        })
+    }
+
+    /// Whether `item` matches any include rule, if there are any, and no exclude rule
+    pub fn keeps(&self, item: &DisplayItem) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|m| m.matches(item)))
+            && !self.exclude.iter().any(|m| m.matches(item))
+    }
+}
+
+impl Matcher {
+    fn new(rule: &Rule) -> Result<Matcher, String> {
+        let (field, pattern) = match rule {
+            Rule::Keyword(keyword) => (None, Pattern::Keyword(keyword.to_lowercase())),
+            Rule::Match {
+                field,
+                keyword: Some(keyword),
+                regex: None,
+            } => (*field, Pattern::Keyword(keyword.to_lowercase())),
+            Rule::Match {
+                field,
+                keyword: None,
+                regex: Some(pattern),
+            } => (*field, Pattern::Regex(regex(pattern, false)?)),
+            Rule::Match { .. } => {
+                return Err("A filter rule needs either a keyword or a regex.".to_string())
+            }
+        };
+        Ok(Matcher { field, pattern })
+    }
+
+    fn matches(&self, item: &DisplayItem) -> bool {
+        let fields = match self.field {
+            Some(field) => vec![field],
+            None => vec![
+                ItemField::Title,
+                ItemField::Description,
+                ItemField::Author,
+                ItemField::Link,
+                ItemField::Category,
+            ],
+        };
+        fields
+            .into_iter()
+            .flat_map(|field| field_values(item, field))
+            .any(|value| match &self.pattern {
+                Pattern::Keyword(keyword) => value.to_lowercase().contains(keyword),
+                Pattern::Regex(regex) => regex.is_match(value),
+            })
+    }
+}
+
+/// The values of `field` in `item`, of which there may be several categories
+fn field_values(item: &DisplayItem, field: ItemField) -> Vec<&str> {
+    match field {
+        ItemField::Title => item.title.as_deref().into_iter().collect(),
+        ItemField::Description => item.description.as_deref().into_iter().collect(),
+        ItemField::Author => item.author.as_deref().into_iter().collect(),
+        ItemField::Link => item.link.as_deref().into_iter().collect(),
+        ItemField::Category => item.categories.iter().map(String::as_str).collect(),
+    }
+}
+
+fn regex(pattern: &str, case_insensitive: bool) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(case_insensitive)
+        .build()
+        .map_err(|err| format!("Invalid filter regex {}: [{}]", pattern, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::item;
+
+    fn rules(yaml: &str) -> Filter {
+        Filter::new(&serde_yaml::from_str(yaml).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn no_rules_keep_everything() {
+        assert!(Filter::default().keeps(&DisplayItem::default()));
+        assert!(rules("{}").keeps(&item("a", "Rust 2.0")));
+    }
+
+    #[test]
+    fn items_matching_any_include_rule_are_kept() {
+        let filter = rules("include: [rust, zig]");
+        assert!(filter.keeps(&item("a", "Rust 2.0")));
+        assert!(filter.keeps(&item("a", "News").with_categories(&["Zig"])));
+        assert!(!filter.keeps(&item("a", "Go 2.0")));
+    }
+
+    #[test]
+    fn items_matching_any_exclude_rule_are_dropped() {
+        let filter = rules("include: [rust]\nexclude: [sponsored, crypto]");
+        assert!(filter.keeps(&item("a", "Rust 2.0")));
+        assert!(!filter.keeps(&item("a", "Rust 2.0 (sponsored)")));
+        assert!(!filter.keeps(&item("a", "Rust 2.0").with_categories(&["crypto"])));
+        let exclude_only = rules("exclude: [crypto]");
+        assert!(exclude_only.keeps(&item("a", "Go 2.0")));
+    }
+
+    #[test]
+    fn keywords_match_any_field_case_insensitively() {
+        let filter = rules("include: [ANN]");
+        assert!(filter.keeps(&item("a", "Rust 2.0").with_author("Ann")));
+        assert!(filter.keeps(&item("a", "[ann] Rust 2.0").with_author("bob")));
+        assert!(!filter.keeps(&item("a", "Rust 2.0").with_author("bob")));
+    }
+
+    #[test]
+    fn field_rules_only_match_their_field() {
+        let keyword = rules("include: [{field: author, keyword: ANN}]");
+        assert!(keyword.keeps(&item("a", "Rust 2.0").with_author("ann")));
+        assert!(!keyword.keeps(&item("a", "[ann] Rust 2.0").with_author("bob")));
+        let category = rules("exclude: [{field: category, keyword: ads}]");
+        assert!(!category.keeps(&item("a", "News").with_categories(&["rust", "Ads"])));
+        assert!(category.keeps(&item("a", "Ads").with_categories(&["rust"])));
+    }
+
+    #[test]
+    fn regexes_are_case_sensitive_unless_grepping() {
+        let regex = rules(r"include: [{field: title, regex: '^Rust \d'}]");
+        assert!(regex.keeps(&item("a", "Rust 2.0")));
+        assert!(!regex.keeps(&item("a", "rust 2.0")));
+        assert!(!regex.keeps(&item("a", "New Rust 2.0")));
+        let grep = Filter::grep(r"^rust \d").unwrap();
+        assert!(grep.keeps(&item("a", "Rust 2.0")));
+        assert!(grep.keeps(&item("a", "News").with_categories(&["RUST 2"])));
+        assert!(!grep.keeps(&item("a", "News")));
+    }
+
+    #[test]
+    fn rules_need_either_a_keyword_or_a_regex() {
+        for yaml in [
+            "include: [{field: title}]",
+            "exclude: [{keyword: a, regex: b}]",
+        ] {
+            let err = Filter::new(&serde_yaml::from_str(yaml).unwrap()).unwrap_err();
+            assert_eq!(err, "A filter rule needs either a keyword or a regex.");
+        }
+        let err =
+            Filter::new(&serde_yaml::from_str("include: [{regex: '('}]").unwrap()).unwrap_err();
+        assert!(err.starts_with("Invalid filter regex ("), "{}", err);
+    }
+}
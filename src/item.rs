@@ -0,0 +1,338 @@
+use std::fmt::Write;
+use std::sync::LazyLock;
+
+use atom_syndication::{Entry, TextType};
+use chrono::{DateTime, FixedOffset};
+use colored::{Color, ColoredString, Colorize};
+use rss::Item;
+use textwrap::Options;
+
+use crate::config::{ChanConfig, ItemConfig};
+use crate::jsonfeed;
+
+const MAX_WIDTH: usize = 80;
+const INDENT: &str = "     ";
+
+static WRAP_OPTIONS: LazyLock<Options> = LazyLock::new(|| {
+    Options::new(MAX_WIDTH)
+        .initial_indent(INDENT)
+        .subsequent_indent(INDENT)
+});
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DisplayItem {
+    /// Channel name/title
+    pub chan_title: String,
+    /// User defined alias of the channel
+    pub chan_alias: Option<String>,
+    /// User defined color of the channel name
+    pub chan_color: Option<Color>,
+    /// User defined marker shown before the channel name
+    pub chan_emoji: Option<String>,
+    /// Group the channel is shown under
+    pub chan_group: Option<String>,
+    /// Url of the channel the item was fetched from
+    pub chan_url: String,
+    /// Item config
+    pub conf: ItemConfig,
+    /// A unique identifier for the item within its channel.
+    pub id: Option<String>,
+    /// The title of the item.
+    pub title: Option<String>,
+    /// The URL of the item.
+    pub link: Option<String>,
+    /// The item synopsis.
+    pub description: Option<String>,
+    /// Whether the description is HTML rather than plain text.
+    pub description_is_html: bool,
+    /// The email address of author of the item.
+    pub author: Option<String>,
+    /// The date the item was published as an RFC 2822 timestamp.
+    pub pub_date: Option<DateTime<FixedOffset>>,
+    /// The description of a media object that is attached to the item.
+    pub enclosure_url: Option<String>,
+    /// The categories or tags of the item.
+    pub categories: Vec<String>,
+    /// The same item as it appeared in other channels.
+    pub duplicates: Vec<DisplayItem>,
+}
+
+impl DisplayItem {
+    /// An empty item of the channel titled `chan_title`, configured by `chan`
+    fn of_channel(chan_title: &str, chan: &ChanConfig) -> DisplayItem {
+        DisplayItem {
+            chan_title: chan_title.to_string(),
+            chan_alias: chan.alias.clone(),
+            chan_color: chan.color.as_deref().and_then(|c| c.parse().ok()),
+            chan_emoji: chan.emoji.clone(),
+            chan_group: chan.groups.first().cloned(),
+            chan_url: chan.location().to_owned(),
+            conf: chan.item_config.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    /// Create a new DisplayItem from RSS Item
+    pub fn new(item: Item, chan_title: &str, chan: &ChanConfig) -> DisplayItem {
+        DisplayItem {
+            id: item
+                .guid
+                .map(|g| g.value)
+                .or_else(|| item.link.clone())
+                .or_else(|| item.title.clone()),
+            title: item.title,
+            link: item.link,
+            description: item.description,
+            description_is_html: true,
+            author: item.author,
+            pub_date: item
+                .pub_date
+                .and_then(|d| DateTime::<FixedOffset>::parse_from_rfc2822(&d).ok()),
+            enclosure_url: item.enclosure.map(|e| e.url),
+            categories: item.categories.into_iter().map(|c| c.name).collect(),
+            ..DisplayItem::of_channel(chan_title, chan)
+        }
+    }
+
+    /// Create a new DisplayItem from an Atom Entry
+    pub fn from_atom(entry: Entry, chan_title: &str, chan: &ChanConfig) -> DisplayItem {
+        let find_link = |rel: &str| {
+            entry
+                .links
+                .iter()
+                .find(|l| l.rel == rel)
+                .map(|l| l.href.clone())
+        };
+        let link = find_link("alternate").or_else(|| entry.links.first().map(|l| l.href.clone()));
+        let enclosure_url = find_link("enclosure");
+        let description = entry
+            .summary
+            .map(|s| (s.value, s.r#type != TextType::Text))
+            .or_else(|| {
+                let content = entry.content?;
+                let is_html = matches!(content.content_type.as_deref(), Some("html" | "xhtml"));
+                content.value.map(|value| (value, is_html))
+            });
+        DisplayItem {
+            id: Some(entry.id),
+            title: Some(entry.title.value),
+            link,
+            description_is_html: description.as_ref().is_some_and(|(_, is_html)| *is_html),
+            description: description.map(|(value, _)| value),
+            author: entry.authors.into_iter().next().map(|a| a.name),
+            pub_date: entry.published.or(Some(entry.updated)),
+            enclosure_url,
+            categories: entry
+                .categories
+                .into_iter()
+                .map(|c| c.label.unwrap_or(c.term))
+                .collect(),
+            ..DisplayItem::of_channel(chan_title, chan)
+        }
+    }
+
+    /// Create a new DisplayItem from a JSON Feed Item
+    pub fn from_json_feed(
+        item: jsonfeed::Item,
+        chan_title: &str,
+        chan: &ChanConfig,
+    ) -> DisplayItem {
+        DisplayItem {
+            id: Some(item.id),
+            title: item.title,
+            link: item.url,
+            description_is_html: item.content_text.is_none()
+                && item.summary.is_none()
+                && item.content_html.is_some(),
+            description: item.content_text.or(item.summary).or(item.content_html),
+            author: item
+                .authors
+                .into_iter()
+                .chain(item.author)
+                .find_map(|a| a.name),
+            pub_date: item
+                .date_published
+                .and_then(|d| DateTime::parse_from_rfc3339(&d).ok()),
+            enclosure_url: item.attachments.into_iter().next().map(|a| a.url),
+            categories: item.tags,
+            ..DisplayItem::of_channel(chan_title, chan)
+        }
+    }
+
+    /// Name of the channel: its alias if it has one, else its title
+    pub fn chan_name(&self) -> &str {
+        self.chan_alias.as_deref().unwrap_or(&self.chan_title)
+    }
+
+    /// Displayed name of the channel, prefixed by its emoji
+    pub fn chan_label(&self) -> String {
+        match &self.chan_emoji {
+            Some(emoji) => format!("{} {}", emoji, self.chan_name()),
+            None => self.chan_name().to_string(),
+        }
+    }
+
+    /// Channel label in the channel's color, bright green by default
+    pub fn styled_chan_label(&self) -> ColoredString {
+        self.chan_label()
+            .color(self.chan_color.unwrap_or(Color::BrightGreen))
+    }
+
+    /// Names of the other channels this item appeared in
+    pub fn also_in(&self) -> String {
+        self.duplicates
+            .iter()
+            .map(|d| d.chan_label())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Build formatted RSS Item
+    pub fn format(&self, show_channel: bool) -> Result<String, std::fmt::Error> {
+        let mut out = String::new();
+        let pub_date = self.pub_date.filter(|_| !self.conf.hide_pub_date);
+        // Datetime and channel name, which is left out when items are grouped under it
+        match (pub_date, show_channel) {
+            (Some(pub_date), true) => {
+                write!(out, "{}", format!("[{}] - ", pub_date.naive_local()).bold())?;
+                writeln!(out, "{}", self.styled_chan_label().underline())?;
+            }
+            (Some(pub_date), false) => {
+                writeln!(out, "{}", format!("[{}]", pub_date.naive_local()).bold())?;
+            }
+            (None, true) => writeln!(out, "{}", self.styled_chan_label().underline())?,
+            (None, false) => {}
+        }
+        // Title
+        if let Some(title) = &self.title
+            && !self.conf.hide_title
+        {
+            writeln!(out, "{}", textwrap::fill(title, &*WRAP_OPTIONS))?;
+        }
+        // Author
+        if let Some(author) = &self.author
+            && !self.conf.hide_author
+        {
+            writeln!(out, " - {}", author)?;
+        }
+        // Other channels with the same item
+        if !self.duplicates.is_empty() {
+            writeln!(out, "{}", format!(" (also in {})", self.also_in()).dimmed())?;
+        }
+        // Description
+        if let Some(desc) = &self.description
+            && !self.conf.hide_description
+        {
+            writeln!(
+                out,
+                "{}",
+                render_description(desc, self.description_is_html)
+            )?;
+        }
+        // Enclosure
+        if let Some(enclosure_url) = &self.enclosure_url
+            && self.conf.show_enclosure
+        {
+            writeln!(out, "[{}]", enclosure_url)?;
+        }
+        // Link
+        if let Some(link) = &self.link
+            && !self.conf.hide_link
+        {
+            writeln!(out, "[{}]", link.bright_blue())?;
+        }
+        Ok(out)
+    }
+}
+
+/// An item titled `title` of the channel titled and located at `chan`,
+/// with other fields set by the methods below
+#[cfg(test)]
+pub fn item(chan: &str, title: &str) -> DisplayItem {
+    DisplayItem {
+        chan_title: chan.to_string(),
+        chan_url: chan.to_string(),
+        title: Some(title.to_string()),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+impl DisplayItem {
+    /// Set the publication date from an RFC 3339 timestamp
+    pub fn dated(self, date: &str) -> DisplayItem {
+        DisplayItem {
+            pub_date: Some(DateTime::parse_from_rfc3339(date).unwrap()),
+            ..self
+        }
+    }
+
+    pub fn with_id(self, id: &str) -> DisplayItem {
+        DisplayItem {
+            id: Some(id.to_string()),
+            ..self
+        }
+    }
+
+    pub fn with_link(self, link: &str) -> DisplayItem {
+        DisplayItem {
+            link: Some(link.to_string()),
+            ..self
+        }
+    }
+
+    pub fn with_author(self, author: &str) -> DisplayItem {
+        DisplayItem {
+            author: Some(author.to_string()),
+            ..self
+        }
+    }
+
+    pub fn with_categories(self, categories: &[&str]) -> DisplayItem {
+        DisplayItem {
+            categories: categories.iter().map(|c| c.to_string()).collect(),
+            ..self
+        }
+    }
+}
+
+/// Titles of `items`, in order
+#[cfg(test)]
+pub fn titles(items: &[DisplayItem]) -> Vec<&str> {
+    items.iter().filter_map(|i| i.title.as_deref()).collect()
+}
+
+/// Convert an item description to plain text wrapped at `width`.
+/// HTML is converted to plain text, with links collected as numbered footnotes.
+pub fn description_text(desc: &str, is_html: bool, width: usize) -> String {
+    // plain text may contain `<` and `&` of its own, e.g. `Vec<String>`
+    if !is_html || !desc.contains(['<', '&']) {
+        return textwrap::fill(desc, width);
+    }
+    match html2text::config::plain_no_decorate()
+        .link_footnotes(true)
+        .string_from_read(desc.as_bytes(), width)
+    {
+        Ok(text) => text.trim_end().to_string(),
+        Err(_) => textwrap::fill(desc, width),
+    }
+}
+
+/// Render an item description as wrapped, indented terminal text
+fn render_description(desc: &str, is_html: bool) -> String {
+    let text = description_text(desc, is_html, MAX_WIDTH - INDENT.len());
+    textwrap::indent(&text, INDENT).trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn description_text_only_renders_html_descriptions() {
+        let plain = "Use Vec<String> & HashMap<K, V> for this";
+        assert_eq!(description_text(plain, false, 80), plain);
+        assert_eq!(description_text("<p>a &amp; b</p>", true, 80), "a & b");
+        assert_eq!(description_text("one two three", true, 8), "one two\nthree");
+    }
+}
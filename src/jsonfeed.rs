@@ -0,0 +1,75 @@
+//! Data model for JSON Feed 1.1 (https://www.jsonfeed.org/version/1.1/)
+use serde::{Deserialize, Deserializer};
+
+/// Prefix of the `version` of every JSON Feed, which other JSON documents lack
+const VERSION_PREFIX: &str = "https://jsonfeed.org/version/";
+
+#[derive(Debug, Deserialize)]
+pub struct Feed {
+    pub title: String,
+    #[serde(default)]
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Item {
+    #[serde(deserialize_with = "string_or_number")]
+    pub id: String,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub content_text: Option<String>,
+    #[serde(default)]
+    pub content_html: Option<String>,
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// RFC 3339 timestamp, parsed leniently since some feeds give e.g. only a date
+    #[serde(default)]
+    pub date_published: Option<String>,
+    #[serde(default)]
+    pub authors: Vec<Author>,
+    /// Deprecated in 1.1 in favor of `authors`, but still common
+    #[serde(default)]
+    pub author: Option<Author>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Author {
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Attachment {
+    pub url: String,
+}
+
+/// Whether `body` is a JSON Feed, rather than some other JSON document like
+/// the API a WordPress page links to
+pub fn is_json_feed(body: &[u8]) -> bool {
+    #[derive(Deserialize)]
+    struct Header {
+        version: String,
+    }
+    serde_json::from_slice::<Header>(body).is_ok_and(|h| h.version.starts_with(VERSION_PREFIX))
+}
+
+/// Readers are to treat a non-string `id`, which some feeds use, as a string
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        String(String),
+        Number(serde_json::Number),
+    }
+    Ok(match Id::deserialize(deserializer)? {
+        Id::String(id) => id,
+        Id::Number(id) => id.to_string(),
+    })
+}
@@ -0,0 +1,99 @@
+//! Limits on the number and age of displayed items
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::item::DisplayItem;
+
+/// Keep the newest `max_items` of `items`, which are in feed order.
+/// Dated items rank by date, newest first, ahead of undated ones; items with
+/// the same date, and undated items, rank in feed order. The kept items stay
+/// in feed order.
+pub fn newest(mut items: Vec<DisplayItem>, max_items: usize) -> Vec<DisplayItem> {
+    if items.len() <= max_items {
+        return items;
+    }
+    let mut ranked: Vec<usize> = (0..items.len()).collect();
+    // a stable sort keeps the feed order among equal dates
+    ranked.sort_by(|a, b| items[*b].pub_date.cmp(&items[*a].pub_date));
+    let mut keep = vec![false; items.len()];
+    for idx in ranked.into_iter().take(max_items) {
+        keep[idx] = true;
+    }
+    let mut keep = keep.into_iter();
+    items.retain(|_| keep.next().unwrap_or_default());
+    items
+}
+
+/// Drop the items of `items` published more than `max_age` before `now`.
+/// Undated items are kept, since their age is unknown.
+pub fn within(items: Vec<DisplayItem>, max_age: Duration, now: DateTime<Utc>) -> Vec<DisplayItem> {
+    let Some(cutoff) = TimeDelta::from_std(max_age)
+        .ok()
+        .and_then(|age| now.checked_sub_signed(age))
+    else {
+        return items;
+    };
+    items
+        .into_iter()
+        .filter(|i| i.pub_date.is_none_or(|date| date >= cutoff))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::{item, titles};
+
+    #[test]
+    fn newest_keeps_newest_by_date_in_feed_order() {
+        let items = vec![
+            item("a", "old").dated("2024-01-01T00:00:00Z"),
+            item("a", "newest").dated("2024-01-03T00:00:00Z"),
+            item("a", "oldest").dated("2023-12-01T00:00:00Z"),
+            item("a", "new").dated("2024-01-02T00:00:00+02:00"),
+        ];
+        assert_eq!(titles(&newest(items, 2)), ["newest", "new"]);
+    }
+
+    #[test]
+    fn newest_falls_back_to_feed_order_for_undated_items() {
+        let items = vec![item("a", "first"), item("a", "second"), item("a", "third")];
+        assert_eq!(titles(&newest(items, 2)), ["first", "second"]);
+    }
+
+    #[test]
+    fn newest_ranks_dated_items_before_undated_ones() {
+        let items = vec![
+            item("a", "undated"),
+            item("a", "dated").dated("2020-01-01T00:00:00Z"),
+            item("a", "also undated"),
+        ];
+        assert_eq!(titles(&newest(items, 2)), ["undated", "dated"]);
+    }
+
+    #[test]
+    fn newest_keeps_everything_under_the_limit() {
+        let items = vec![item("a", "a"), item("a", "b").dated("2024-01-01T00:00:00Z")];
+        assert_eq!(titles(&newest(items, 5)), ["a", "b"]);
+        assert!(newest(vec![item("a", "a")], 0).is_empty());
+    }
+
+    #[test]
+    fn within_drops_old_items_and_keeps_undated_ones() {
+        let now = DateTime::parse_from_rfc3339("2024-01-10T00:00:00Z")
+            .unwrap()
+            .to_utc();
+        let items = vec![
+            item("a", "recent").dated("2024-01-09T00:00:00Z"),
+            item("a", "old").dated("2024-01-01T00:00:00Z"),
+            item("a", "undated"),
+            item("a", "edge").dated("2024-01-03T00:00:00Z"),
+        ];
+        let week = Duration::from_secs(7 * 24 * 60 * 60);
+        assert_eq!(
+            titles(&within(items, week, now)),
+            ["recent", "undated", "edge"]
+        );
+    }
+}
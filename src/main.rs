@@ -1,201 +1,209 @@
 #![feature(iterator_try_collect)]
 #![feature(lazy_cell)]
 #![feature(let_chains)]
-use std::collections::BinaryHeap;
+use std::collections::HashMap;
 use std::error::Error;
-use std::fmt::Write;
-use std::fs::OpenOptions;
-use std::io::{BufReader, ErrorKind};
-use std::sync::{Arc, LazyLock, RwLock};
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
 
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, Utc};
 use colored::Colorize;
 use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
-use rss::{Channel, Item};
-use textwrap::Options;
-
-use crate::config::{Config, ItemConfig, Order};
 
+use crate::cache::FeedCache;
+use crate::cli::{Cli, Command, FetchArgs};
+use crate::config::{Config, OutputFormat};
+use crate::fetch::Client;
+use crate::filter::Filter;
+use crate::item::DisplayItem;
+use crate::query::Query;
+use crate::state::{FeedIds, ReadState};
+
+mod cache;
+mod cli;
 mod config;
+mod dedupe;
+mod discover;
+mod feed;
+mod fetch;
+mod filter;
+mod item;
+mod jsonfeed;
+mod limit;
+mod manage;
+mod opml;
+mod output;
+mod query;
+mod sort;
+mod state;
+mod tui;
 
-const MAX_WIDTH: usize = 80;
-
-static WRAP_OPTIONS: LazyLock<Options> = LazyLock::new(|| {
-    Options::new(MAX_WIDTH)
-        .initial_indent("     ")
-        .subsequent_indent("     ")
-});
-
-#[derive(Debug, PartialEq, Eq)]
-struct DisplayItem {
-    /// Channel name/title
-    chan_title: String,
-    /// Item config
-    conf: ItemConfig,
-    /// The title of the item.
-    title: Option<String>,
-    /// The URL of the item.
-    link: Option<String>,
-    /// The item synopsis.
-    description: Option<String>,
-    /// The email address of author of the item.
-    author: Option<String>,
-    /// The date the item was published as an RFC 2822 timestamp.
-    pub_date: Option<DateTime<FixedOffset>>,
-    /// The description of a media object that is attached to the item.
-    enclosure_url: Option<String>,
+fn main() -> Result<(), Box<dyn Error>> {
+    // get cli flags
+    let cli = Cli::from_env();
+    let config_path = cli.config.unwrap_or_else(Config::default_path);
+
+    match cli.command {
+        None => fetch(&config_path, FetchArgs::default(), false),
+        Some(Command::Fetch(args)) => fetch(&config_path, args, false),
+        Some(Command::Tui(args)) => fetch(&config_path, args.into(), true),
+        Some(Command::Add(args)) => manage::add(&config_path, args),
+        Some(Command::Remove(args)) => manage::remove(&config_path, args),
+        Some(Command::List(_)) => manage::list(&config_path),
+        Some(Command::Edit(_)) => manage::edit(&config_path),
+        Some(Command::Validate(_)) => manage::validate(&config_path),
+        Some(Command::Import(args)) => manage::import(&config_path, args),
+        Some(Command::Export(_)) => manage::export(&config_path),
+    }
 }
 
-impl DisplayItem {
-    /// Create a new DisplayItem from RSS Item
-    pub fn new(item: Item, chan_title: &str, conf: &ItemConfig) -> DisplayItem {
-        DisplayItem {
-            chan_title: chan_title.to_string(),
-            conf: conf.to_owned(),
-            title: item.title,
-            link: item.link,
-            description: item.description,
-            author: item.author,
-            pub_date: item
-                .pub_date
-                .and_then(|d| DateTime::<FixedOffset>::parse_from_rfc2822(&d).ok()),
-            enclosure_url: item.enclosure.map(|e| e.url),
-        }
+/// Fetch the configured channels, or those given in `args`, and display their items
+fn fetch(config_path: &Path, args: FetchArgs, tui: bool) -> Result<(), Box<dyn Error>> {
+    // open config file if present, and override with any flags provided
+    let config = Config::load(config_path)?
+        .unwrap_or_default()
+        .override_with(args);
+
+    config.validate()?;
+
+    let (items, feed_ids) = fetch_items(&config)?;
+    let mut items = dedupe::dedupe(items, config.dedupe);
+
+    let mut read_state = if config.unread || config.mark_read || tui {
+        let mut read_state = ReadState::load()?;
+        read_state.forget_removed(&feed_ids);
+        read_state
+    } else {
+        ReadState::default()
+    };
+
+    if config.unread {
+        items.retain(|i| !read_state.is_read(i));
     }
 
-    /// Build formatted RSS Item
-    pub fn format(&self) -> Result<String, std::fmt::Error> {
-        use colored::*;
-        let mut out = String::new();
-        // Datetime
-        if let Some(pub_date) = self.pub_date
-            && !self.conf.hide_pub_date
-        {
-            write!(out, "{}", format!("[{}] - ", pub_date.naive_local()).bold())?;
-        }
-        // Channel title
-        writeln!(out, "{}", self.chan_title.bright_green().underline())?;
-        // Title
-        if let Some(title) = &self.title
-            && !self.conf.hide_title
-        {
-            writeln!(out, "{}", textwrap::fill(title, &*WRAP_OPTIONS))?;
-        }
-        // Author
-        if let Some(author) = &self.author
-            && !self.conf.hide_author
-        {
-            writeln!(out, " - {}", author)?;
-        }
-        // Description
-        if let Some(desc) = &self.description
-            && !self.conf.hide_description
-        {
-            writeln!(out, "{}", textwrap::fill(desc, &*WRAP_OPTIONS))?;
-        }
-        // Enclosure
-        if let Some(enclosure_url) = &self.enclosure_url
-            && self.conf.show_enclosure
-        {
-            writeln!(out, "[{}]", enclosure_url)?;
-        }
-        // Link
-        if let Some(link) = &self.link
-            && !self.conf.hide_link
-        {
-            writeln!(out, "[{}]", link.bright_blue())?;
-        }
-        Ok(out)
+    let now = DateTime::<Utc>::from(SystemTime::now());
+    if let Some(query) = &config.filter {
+        items.retain(|i| query.matches(i, now));
+    }
+    if let Some(view) = &config.view {
+        // views were checked to parse when validating the config
+        let query: Query = config.views[view].parse()?;
+        items.retain(|i| query.matches(i, now));
     }
-}
 
-impl Ord for DisplayItem {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.pub_date.cmp(&other.pub_date)
+    if let Some(max_items) = config.limit {
+        items = limit::newest(items, max_items);
     }
-}
 
-impl PartialOrd for DisplayItem {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+    let output = config.output;
+    if items.is_empty() && (tui || matches!(output, OutputFormat::Text)) {
+        eprintln!("No RSS items found.");
+        return Ok(());
     }
-}
 
-fn main() -> Result<(), Box<dyn Error>> {
-    // get cli flags
-    let args: Config = argh::from_env();
+    sort::sort_items(&mut items, &config);
 
-    // open config file if present
-    let config = dirs::config_dir()
-        .map(|mut d| {
-            d.push("rssget/config.yaml");
-            d
-        })
-        .expect("could not determine system config directory");
-    let config = match OpenOptions::new().read(true).open(config) {
-        Ok(file) => {
-            // parse config file
-            let config_file: Config = serde_yaml::from_reader(&file)?;
-            // override with any flags provided
-            config_file.override_with(args)
-        }
-        Err(err) if err.kind() == ErrorKind::NotFound => {
-            // config file not found, try to use flags
-            args
-        }
-        Err(err) => return Err(Box::new(err)),
-    };
+    if tui {
+        return tui::run(items, read_state);
+    }
 
-    config.validate()?;
+    output::write(&items, output, config.display_by, io::stdout().lock())?;
+
+    if config.mark_read {
+        items.iter().for_each(|i| read_state.mark_read(i));
+        read_state.save()?;
+    }
+
+    Ok(())
+}
 
+/// Fetch and parse all configured channels in parallel, reporting progress
+/// and any errors to stderr. Also returns the ids of all items in each channel
+/// that could be read, before any filters or limits.
+fn fetch_items(config: &Config) -> Result<(Vec<DisplayItem>, FeedIds), Box<dyn Error>> {
     let progress_bar = ProgressBar::new(config.channels.len().try_into()?);
     progress_bar.set_style(
-        ProgressStyle::with_template(
-            "Fetching RSS… [{bar:40.green/white}] {pos:>2}/{len:2} {msg:.green}",
-        )
+        ProgressStyle::with_template(if config.offline {
+            "Loading cached RSS… [{bar:40.green/white}] {pos:>2}/{len:2} {msg:.green}"
+        } else {
+            "Fetching RSS… [{bar:40.green/white}] {pos:>2}/{len:2} {msg:.green}"
+        })
         .unwrap()
         .progress_chars("==>~"),
     );
 
     // call out to all rss feeds
-    let http = ureq::agent();
+    let cache = FeedCache::open();
+    let now = DateTime::<Utc>::from(SystemTime::now());
+    let mut filters = vec![Filter::new(&config.filters)?];
+    if let Some(pattern) = &config.grep {
+        filters.push(Filter::grep(pattern)?);
+    }
     let errors = Arc::new(RwLock::new(vec![]));
-    let mut items = config
+    let notices = Arc::new(RwLock::new(vec![]));
+    let feed_ids = Arc::new(RwLock::new(HashMap::new()));
+    let items = config
         .channels
         .par_iter()
         .flat_map(|conf| {
             progress_bar.set_message(
                 conf.alias
                     .clone()
-                    .unwrap_or_else(|| format!("{:.20}", conf.url)),
+                    .unwrap_or_else(|| format!("{:.20}", conf.location())),
             );
-            let items = match http.get(&conf.url).call() {
-                Ok(res) => match Channel::read_from(BufReader::new(res.into_reader())) {
-                    Ok(chan) => Ok(chan
-                        .items
-                        .into_iter()
-                        .map(|item| {
-                            DisplayItem::new(
-                                item,
-                                &chan.title,
-                                &conf.item_config.unwrap_or_default(),
-                            )
-                        })
-                        .collect::<BinaryHeap<DisplayItem>>()),
-                    Err(err) => {
-                        Err(format!("Could not parse rss response from chan: [{}]", err).red())
-                    }
-                },
-                Err(err) => Err(format!("Could not reach rss: [{}]", err).red()),
+            let local = if config.offline && conf.command.is_some() {
+                None
+            } else {
+                fetch::read_local(conf)
+            };
+            let body = if let Some(body) = local {
+                body
+            } else if config.offline {
+                fetch::fetch_cached(&cache, conf).map(|(body, age)| {
+                    notices.write().unwrap().push(
+                        format!(
+                            "Using cached copy of {} from {}",
+                            conf.alias.as_ref().unwrap_or(&conf.url),
+                            age
+                        )
+                        .dimmed(),
+                    );
+                    body
+                })
+            } else {
+                Client::for_channel(&config.http, conf)
+                    .and_then(|client| fetch::fetch(&client, &cache, conf))
             };
+            let items = body
+                .and_then(|body| feed::parse(&body, conf))
+                .and_then(|items| {
+                    let ids = items.iter().filter_map(|i| i.id.clone()).collect();
+                    feed_ids
+                        .write()
+                        .unwrap()
+                        .insert(conf.location().to_owned(), ids);
+                    let chan_filter = Filter::new(&conf.filters)?;
+                    Ok(items
+                        .into_iter()
+                        .filter(|i| chan_filter.keeps(i) && filters.iter().all(|f| f.keeps(i)))
+                        .collect::<Vec<_>>())
+                })
+                .map_err(|err| err.red());
             progress_bar.inc(1);
 
             match items {
-                Ok(mut items) => items
-                    .drain()
-                    .take(conf.max_items.unwrap_or(usize::MAX))
-                    .collect(),
+                Ok(mut items) => {
+                    if let Some(max_age) = conf.max_age.or(config.max_age) {
+                        items = limit::within(items, max_age, now);
+                    }
+                    match conf.max_items {
+                        Some(max_items) => limit::newest(items, max_items),
+                        None => items,
+                    }
+                }
                 Err(e) => {
                     errors.write().unwrap().push(e);
                     vec![]
@@ -204,26 +212,17 @@ fn main() -> Result<(), Box<dyn Error>> {
         })
         .collect::<Vec<DisplayItem>>();
 
+    notices
+        .read()
+        .unwrap()
+        .iter()
+        .for_each(|n| eprintln!("{}", n));
     errors
         .read()
         .unwrap()
         .iter()
         .for_each(|e| eprintln!("{}", e));
 
-    if items.is_empty() {
-        eprintln!("No RSS items found.");
-        return Ok(());
-    }
-
-    items.sort_by_key(|i| match config.display_by {
-        Order::Date => i.pub_date,
-        Order::Channel => None,
-    });
-
-    items.iter().for_each(|i| match i.format() {
-        Ok(output) => println!("{}", output),
-        Err(err) => eprintln!("Could not format RSS Item: {}", err),
-    });
-
-    Ok(())
+    let feed_ids = std::mem::take(&mut *feed_ids.write().unwrap());
+    Ok((items, feed_ids))
 }
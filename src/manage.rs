@@ -0,0 +1,499 @@
+//! Commands that manage the channels of the config file.
+//!
+//! Edits are made to the text of the config file where possible, so that
+//! comments and formatting survive. If the file has a layout that can't be
+//! edited in place, it is rewritten from the parsed config instead.
+use std::error::Error;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process;
+
+use colored::Colorize;
+
+use crate::cli::{AddArgs, ImportArgs, RemoveArgs};
+use crate::config::{ChanConfig, Config};
+use crate::fetch::{self, Client};
+use crate::opml;
+
+/// Template for a config file created by `edit`. The annotated example config
+/// isn't used, since its example channels would run commands and fetch urls.
+const CONFIG_TEMPLATE: &str = concat!(
+    "# rssget configuration file, all settings are described in\n# ",
+    env!("CARGO_PKG_REPOSITORY"),
+    "/blob/main/config.yaml\n",
+    "\n",
+    "# channels to retrieve, e.g.\n",
+    "#   - url: \"https://example.com/rss\"\n",
+    "#     alias: \"Example RSS\"\n",
+    "channels: []\n",
+);
+
+/// Add a channel to the config file, creating it if needed.
+/// If the url points at a web page, the feed it links to is added instead.
+/// Files and stdin are added as given.
+pub fn add(path: &Path, args: AddArgs) -> Result<(), Box<dyn Error>> {
+    let url = if fetch::is_local(&args.url) {
+        args.url
+    } else {
+        let settings = Config::load(path)?.unwrap_or_default().http;
+        let client = Client::new(&settings, &args.url)?;
+        match fetch::resolve_feed_url(&client, &args.url) {
+            Ok(Some(url)) => {
+                if url != args.url {
+                    println!("Found feed {}", url.green());
+                }
+                url
+            }
+            Ok(None) => return Err(format!("Could not find a feed at: [{}]", args.url).into()),
+            // the channel may just be unreachable right now, keep it as given
+            Err(err) => {
+                eprintln!("{}", err.yellow());
+                args.url
+            }
+        }
+    };
+    let chan = ChanConfig {
+        url,
+        alias: args.alias,
+        max_items: args.max_items,
+        groups: args.group,
+        ..Default::default()
+    };
+    let name = chan.alias.clone().unwrap_or_else(|| chan.url.clone());
+    if add_channels(path, vec![chan])? == 0 {
+        return Err(format!("Channel {} is already configured.", name).into());
+    }
+    println!("Added {}", name.green());
+    Ok(())
+}
+
+/// Add the channels of an OPML file to the config file, skipping those already configured
+pub fn import(path: &Path, args: ImportArgs) -> Result<(), Box<dyn Error>> {
+    let text = fs::read_to_string(&args.file)
+        .map_err(|err| format!("{}: {}", args.file.display(), err))?;
+    let channels = opml::parse(&text)?;
+    let found = channels.len();
+    let added = add_channels(path, channels)?;
+    println!(
+        "Imported {} of {} channel(s) from {}",
+        added.to_string().green(),
+        found,
+        args.file.display()
+    );
+    Ok(())
+}
+
+/// Print the configured channels as OPML
+pub fn export(path: &Path) -> Result<(), Box<dyn Error>> {
+    let config = Config::load(path)?.unwrap_or_default();
+    print!("{}", opml::to_string(&config.channels)?);
+    Ok(())
+}
+
+/// Append `channels` to the config file, skipping those already configured.
+/// Returns the number of channels added.
+fn add_channels(path: &Path, channels: Vec<ChanConfig>) -> Result<usize, Box<dyn Error>> {
+    let text = read_config_text(path)?;
+    let mut config: Config = parse(&text)?;
+    let mut new_channels: Vec<ChanConfig> = vec![];
+    for chan in channels {
+        let known = |c: &ChanConfig| c.url == chan.url;
+        if !config.channels.iter().any(known) && !new_channels.iter().any(known) {
+            new_channels.push(chan);
+        }
+    }
+    if new_channels.is_empty() {
+        return Ok(0);
+    }
+
+    let mut edited = text;
+    for chan in &new_channels {
+        edited = insert_channel(&edited, chan)?;
+    }
+    let expected: Vec<&str> = config
+        .channels
+        .iter()
+        .chain(&new_channels)
+        .map(|c| c.url.as_str())
+        .collect();
+    let added = new_channels.len();
+    let edited = match parse(&edited) {
+        Ok(new) if new.channels.iter().map(|c| c.url.as_str()).eq(expected) => edited,
+        _ => {
+            config.channels.extend(new_channels);
+            serde_yaml::to_string(&config)?
+        }
+    };
+    write_config(path, &edited)?;
+    Ok(added)
+}
+
+/// Remove a channel, matched by url or alias, from the config file
+pub fn remove(path: &Path, args: RemoveArgs) -> Result<(), Box<dyn Error>> {
+    let text = read_existing_config_text(path)?;
+    let mut config: Config = parse(&text)?;
+    let Some(idx) = config
+        .channels
+        .iter()
+        .position(|c| c.location() == args.channel || c.alias.as_ref() == Some(&args.channel))
+    else {
+        return Err(format!("No channel matching {} is configured.", args.channel).into());
+    };
+    let removed = config.channels[idx].location().to_owned();
+
+    let edited = match delete_channel(&text, idx).map(|edited| (parse(&edited), edited)) {
+        Some((Ok(new), edited))
+            if new.channels.len() + 1 == config.channels.len()
+                && new.channels.iter().all(|c| c.location() != removed) =>
+        {
+            edited
+        }
+        _ => {
+            config.channels.remove(idx);
+            serde_yaml::to_string(&config)?
+        }
+    };
+    write_config(path, &edited)?;
+    println!("Removed {}", args.channel.green());
+    Ok(())
+}
+
+/// Print the configured channels
+pub fn list(path: &Path) -> Result<(), Box<dyn Error>> {
+    let config = Config::load(path)?.unwrap_or_default();
+    let width = config
+        .channels
+        .iter()
+        .filter_map(|c| c.alias.as_ref().map(|a| a.chars().count()))
+        .max()
+        .unwrap_or(0);
+    for chan in &config.channels {
+        let alias = chan.alias.as_deref().unwrap_or_default();
+        println!(
+            "{:width$}  {}",
+            alias.bright_green(),
+            chan.location(),
+            width = width
+        );
+    }
+    Ok(())
+}
+
+/// Open the config file in the user's editor, then validate it
+pub fn edit(path: &Path) -> Result<(), Box<dyn Error>> {
+    if !path.exists() {
+        write_config(path, CONFIG_TEMPLATE)?;
+    }
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // the editor may come with its own arguments, e.g. `code --wait`
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = process::Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .map_err(|err| format!("Could not start editor {}: [{}]", editor, err))?;
+    if !status.success() {
+        return Err(format!("Editor {} exited with {}", editor, status).into());
+    }
+    validate(path)
+}
+
+/// Check that the config file parses and is usable
+pub fn validate(path: &Path) -> Result<(), Box<dyn Error>> {
+    let text = read_existing_config_text(path)?;
+    let config = parse(&text).map_err(|err| format!("{}: {}", path.display(), err))?;
+    config.validate()?;
+    println!(
+        "{} is valid, {} channel(s) configured.",
+        path.display(),
+        config.channels.len()
+    );
+    Ok(())
+}
+
+fn parse(text: &str) -> Result<Config, serde_yaml::Error> {
+    // an empty document, or one with only comments, is an empty config
+    if text
+        .lines()
+        .all(|l| l.trim().is_empty() || l.trim_start().starts_with('#'))
+    {
+        return Ok(Config::default());
+    }
+    serde_yaml::from_str(text)
+}
+
+fn read_config_text(path: &Path) -> Result<String, Box<dyn Error>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(String::new()),
+        Err(err) => Err(Box::new(err)),
+    }
+}
+
+fn read_existing_config_text(path: &Path) -> Result<String, Box<dyn Error>> {
+    fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err).into())
+}
+
+fn write_config(path: &Path, text: &str) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, text)?;
+    Ok(())
+}
+
+/// Line range of the top level `channels` key and the indentation of its items
+struct ChannelsBlock {
+    /// Index of the `channels:` line
+    key: usize,
+    /// Index one past the last line belonging to the block
+    end: usize,
+    /// Indentation of the `-` of each item
+    indent: usize,
+}
+
+fn is_content(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    !trimmed.is_empty() && !trimmed.starts_with('#')
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// The value of a top level `channels` key on `line`, without any trailing comment
+fn channels_value(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix("channels:")?;
+    // a comment starts with a `#` after whitespace
+    let comment = rest
+        .char_indices()
+        .find(|(idx, c)| *c == '#' && (*idx == 0 || rest[..*idx].ends_with([' ', '\t'])))
+        .map_or(rest.len(), |(idx, _)| idx);
+    Some(rest[..comment].trim())
+}
+
+/// Find the block style list under the top level `channels` key
+fn find_channels_block(lines: &[&str]) -> Option<ChannelsBlock> {
+    let key = lines.iter().position(|l| channels_value(l) == Some(""))?;
+    let mut end = key + 1;
+    let mut indent = None;
+    for (idx, line) in lines.iter().enumerate().skip(key + 1) {
+        if !is_content(line) {
+            continue;
+        }
+        let line_indent = indent_of(line);
+        // a new top level key ends the block
+        if line_indent == 0 && !line.starts_with('-') {
+            break;
+        }
+        if line.trim_start().starts_with('-') && indent.is_none() {
+            indent = Some(line_indent);
+        }
+        end = idx + 1;
+    }
+    Some(ChannelsBlock {
+        key,
+        end,
+        indent: indent.unwrap_or(2),
+    })
+}
+
+/// Render a string as a YAML scalar, quoting it if needed
+fn yaml_scalar(value: &str) -> Result<String, serde_yaml::Error> {
+    Ok(serde_yaml::to_string(value)?.trim_end().to_string())
+}
+
+/// Append `chan` to the channels list in `text`
+fn insert_channel(text: &str, chan: &ChanConfig) -> Result<String, serde_yaml::Error> {
+    let lines: Vec<&str> = text.lines().collect();
+    let block = find_channels_block(&lines);
+    let indent = " ".repeat(block.as_ref().map_or(2, |b| b.indent));
+
+    let mut entry = vec![format!("{}- url: {}", indent, yaml_scalar(&chan.url)?)];
+    if let Some(alias) = &chan.alias {
+        entry.push(format!("{}  alias: {}", indent, yaml_scalar(alias)?));
+    }
+    if let Some(max_items) = chan.max_items {
+        entry.push(format!("{}  max_items: {}", indent, max_items));
+    }
+    if !chan.groups.is_empty() {
+        let groups = chan
+            .groups
+            .iter()
+            .map(|g| yaml_scalar(g))
+            .collect::<Result<Vec<_>, _>>()?;
+        entry.push(format!("{}  groups: [{}]", indent, groups.join(", ")));
+    }
+
+    let mut out: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+    // turn an empty flow style list into a block style one
+    if block.is_none()
+        && let Some(key) = out.iter().position(|l| channels_value(l) == Some("[]"))
+    {
+        out[key] = out[key].replacen(" []", "", 1);
+        out.splice(key + 1..key + 1, entry);
+        return Ok(out.join("\n") + "\n");
+    }
+    match block {
+        Some(block) => {
+            out.splice(block.end..block.end, entry);
+        }
+        None => {
+            if out.last().is_some_and(|l| !l.trim().is_empty()) {
+                out.push(String::new());
+            }
+            out.push("channels:".to_string());
+            out.extend(entry);
+        }
+    }
+    Ok(out.join("\n") + "\n")
+}
+
+/// Delete the `idx`th item of the channels list in `text`, along with any
+/// comment lines directly above it
+fn delete_channel(text: &str, idx: usize) -> Option<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let block = find_channels_block(&lines)?;
+    let starts: Vec<usize> = (block.key + 1..block.end)
+        .filter(|i| indent_of(lines[*i]) == block.indent && lines[*i].trim_start().starts_with('-'))
+        .collect();
+    let is_comment = |i: usize| lines[i].trim_start().starts_with('#');
+    let mut start = *starts.get(idx)?;
+    let mut end = starts.get(idx + 1).copied().unwrap_or(block.end);
+    while start > block.key + 1 && is_comment(start - 1) {
+        start -= 1;
+    }
+    // comments directly above the next item belong to it
+    if idx + 1 < starts.len() {
+        while end > start && is_comment(end - 1) {
+            end -= 1;
+        }
+    }
+
+    let mut out: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+    out.drain(start..end);
+    if starts.len() == 1 {
+        out[block.key] = lines[block.key].replacen("channels:", "channels: []", 1);
+    }
+    Some(out.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chan(url: &str) -> ChanConfig {
+        ChanConfig {
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn insert_appends_to_the_channels_block() {
+        let text = "\
+# my feeds
+channels:
+  # news
+  - url: a
+    alias: A
+
+# output
+output: text
+";
+        let chan = ChanConfig {
+            alias: Some("New: one".to_string()),
+            groups: vec!["news".to_string()],
+            ..chan("b")
+        };
+        assert_eq!(
+            insert_channel(text, &chan).unwrap(),
+            "\
+# my feeds
+channels:
+  # news
+  - url: a
+    alias: A
+  - url: b
+    alias: 'New: one'
+    groups: [news]
+
+# output
+output: text
+"
+        );
+    }
+
+    #[test]
+    fn insert_keeps_items_at_column_zero() {
+        let text = "channels:\n- url: a\n  alias: A\noutput: text\n";
+        assert_eq!(
+            insert_channel(text, &chan("b")).unwrap(),
+            "channels:\n- url: a\n  alias: A\n- url: b\noutput: text\n"
+        );
+    }
+
+    #[test]
+    fn insert_turns_an_empty_list_into_a_block() {
+        assert_eq!(
+            insert_channel("# feeds\nchannels: []\n", &chan("a")).unwrap(),
+            "# feeds\nchannels:\n  - url: a\n"
+        );
+        assert_eq!(
+            insert_channel("output: text\n", &chan("a")).unwrap(),
+            "output: text\n\nchannels:\n  - url: a\n"
+        );
+    }
+
+    #[test]
+    fn insert_keeps_a_comment_after_the_key() {
+        assert_eq!(
+            insert_channel("channels: # feeds\n  - url: a\n", &chan("b")).unwrap(),
+            "channels: # feeds\n  - url: a\n  - url: b\n"
+        );
+        assert_eq!(
+            insert_channel("channels: [] # feeds\n", &chan("a")).unwrap(),
+            "channels: # feeds\n  - url: a\n"
+        );
+    }
+
+    #[test]
+    fn delete_takes_the_comments_above_an_item() {
+        let text = "\
+channels:
+  # first
+  - url: a
+    alias: A
+  # second
+  - url: b
+output: text
+";
+        assert_eq!(
+            delete_channel(text, 0).unwrap(),
+            "channels:\n  # second\n  - url: b\noutput: text\n"
+        );
+        assert_eq!(
+            delete_channel(text, 1).unwrap(),
+            "channels:\n  # first\n  - url: a\n    alias: A\noutput: text\n"
+        );
+        assert!(delete_channel(text, 2).is_none());
+    }
+
+    #[test]
+    fn delete_at_column_zero() {
+        let text = "channels:\n- url: a\n  alias: A\n- url: b\n";
+        assert_eq!(delete_channel(text, 0).unwrap(), "channels:\n- url: b\n");
+    }
+
+    #[test]
+    fn delete_of_the_last_item_leaves_an_empty_list() {
+        assert_eq!(
+            delete_channel("channels: # feeds\n  - url: a\noutput: text\n", 0).unwrap(),
+            "channels: [] # feeds\noutput: text\n"
+        );
+    }
+}
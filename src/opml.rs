@@ -0,0 +1,227 @@
+//! Conversion between OPML outlines and channel configs
+use serde::{Deserialize, Serialize};
+
+use crate::config::ChanConfig;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename = "opml")]
+struct Opml {
+    #[serde(rename = "@version")]
+    version: String,
+    head: Head,
+    body: Body,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Head {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Body {
+    #[serde(rename = "outline", default)]
+    outlines: Vec<Outline>,
+}
+
+/// An outline is either a feed, if it has an `xmlUrl`, or a folder of outlines
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Outline {
+    #[serde(rename = "@text", default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(rename = "@title", default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(rename = "@type", default, skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+    #[serde(rename = "@xmlUrl", default, skip_serializing_if = "Option::is_none")]
+    xml_url: Option<String>,
+    /// Comma separated category paths, e.g. `/news,/work`
+    #[serde(rename = "@category", default, skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+    #[serde(rename = "outline", default)]
+    outlines: Vec<Outline>,
+}
+
+/// Read the channels of an OPML document.
+/// Folders the channels were in and their categories become their groups.
+pub fn parse(text: &str) -> Result<Vec<ChanConfig>, String> {
+    let opml: Opml =
+        quick_xml::de::from_str(text).map_err(|err| format!("Could not parse OPML: [{}]", err))?;
+    let mut channels = vec![];
+    collect_channels(opml.body.outlines, &mut vec![], &mut channels);
+    Ok(channels)
+}
+
+fn collect_channels(outlines: Vec<Outline>, folders: &mut Vec<String>, out: &mut Vec<ChanConfig>) {
+    for outline in outlines {
+        let name = outline.title.or(outline.text).filter(|n| !n.is_empty());
+        match outline.xml_url {
+            Some(url) => {
+                let mut groups = folders.clone();
+                let categories = outline.category.iter().flat_map(|c| c.split([',', '/']));
+                for category in categories.map(str::trim).filter(|c| !c.is_empty()) {
+                    if !groups.iter().any(|g| g == category) {
+                        groups.push(category.to_string());
+                    }
+                }
+                out.push(ChanConfig {
+                    // readers name feeds without a title after their url
+                    alias: name.filter(|n| *n != url),
+                    url,
+                    groups,
+                    ..Default::default()
+                })
+            }
+            None => {
+                let is_folder = name.is_some();
+                if let Some(name) = name {
+                    folders.push(name);
+                }
+                collect_channels(outline.outlines, folders, out);
+                if is_folder {
+                    folders.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Write `channels` as an OPML document, with a folder for the first group of each channel.
+/// Channels in several groups list all of them as categories.
+/// Channels produced by a command are left out.
+pub fn to_string(channels: &[ChanConfig]) -> Result<String, String> {
+    // `text` is required, so channels without an alias are named by their url
+    let feed = |chan: &ChanConfig| Outline {
+        text: Some(chan.alias.clone().unwrap_or_else(|| chan.url.clone())),
+        title: chan.alias.clone(),
+        kind: Some("rss".to_string()),
+        xml_url: Some(chan.url.clone()),
+        category: (chan.groups.len() > 1).then(|| {
+            chan.groups
+                .iter()
+                .map(|g| format!("/{}", g))
+                .collect::<Vec<_>>()
+                .join(",")
+        }),
+        outlines: vec![],
+    };
+
+    let mut outlines: Vec<Outline> = vec![];
+    for chan in channels.iter().filter(|c| c.command.is_none()) {
+        let Some(group) = chan.groups.first() else {
+            outlines.push(feed(chan));
+            continue;
+        };
+        let folder = match outlines
+            .iter_mut()
+            .position(|o| o.xml_url.is_none() && o.text.as_ref() == Some(group))
+        {
+            Some(idx) => &mut outlines[idx],
+            None => {
+                outlines.push(Outline {
+                    text: Some(group.clone()),
+                    title: Some(group.clone()),
+                    ..Default::default()
+                });
+                outlines.last_mut().unwrap()
+            }
+        };
+        folder.outlines.push(feed(chan));
+    }
+
+    let opml = Opml {
+        version: "2.0".to_string(),
+        head: Head {
+            title: Some("rssget channels".to_string()),
+        },
+        body: Body { outlines },
+    };
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let mut serializer = quick_xml::se::Serializer::new(&mut out);
+    serializer.indent(' ', 2);
+    opml.serialize(serializer)
+        .map_err(|err| format!("Could not write OPML: [{}]", err))?;
+    out.push('\n');
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chan(url: &str, alias: Option<&str>, groups: &[&str]) -> ChanConfig {
+        ChanConfig {
+            url: url.to_string(),
+            alias: alias.map(str::to_string),
+            groups: groups.iter().map(|g| g.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn summary(channels: &[ChanConfig]) -> Vec<(&str, Option<&str>, Vec<&str>)> {
+        channels
+            .iter()
+            .map(|c| {
+                let groups = c.groups.iter().map(String::as_str).collect();
+                (c.url.as_str(), c.alias.as_deref(), groups)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn export_then_import_keeps_channels() {
+        let channels = vec![
+            chan("https://a.com/rss", Some("A & B"), &["news"]),
+            chan("https://b.com/feed", None, &[]),
+            ChanConfig {
+                command: Some("scrape".to_string()),
+                ..Default::default()
+            },
+            chan("https://c.com/atom.xml", None, &["news", "work"]),
+            chan("https://d.com/rss", Some("D"), &["work"]),
+        ];
+        let imported = parse(&to_string(&channels).unwrap()).unwrap();
+        assert_eq!(
+            summary(&imported),
+            [
+                ("https://a.com/rss", Some("A & B"), vec!["news"]),
+                ("https://c.com/atom.xml", None, vec!["news", "work"]),
+                ("https://b.com/feed", None, vec![]),
+                ("https://d.com/rss", Some("D"), vec!["work"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn import_reads_nested_folders_and_names() {
+        let text = r#"<?xml version="1.0"?>
+<opml version="1.0">
+  <head><title>Subscriptions</title></head>
+  <body>
+    <outline text="Tech">
+      <outline text="Lang">
+        <outline type="rss" text="Rust" title="Rust Blog" xmlUrl="https://blog.rust-lang.org/feed.xml"/>
+      </outline>
+      <outline type="rss" text="https://lwn.net/headlines/rss" xmlUrl="https://lwn.net/headlines/rss" category="/Tech, /News/Linux"/>
+    </outline>
+    <outline type="rss" text="Plain" xmlUrl="https://plain.org/rss"/>
+  </body>
+</opml>"#;
+        assert_eq!(
+            summary(&parse(text).unwrap()),
+            [
+                (
+                    "https://blog.rust-lang.org/feed.xml",
+                    Some("Rust Blog"),
+                    vec!["Tech", "Lang"]
+                ),
+                (
+                    "https://lwn.net/headlines/rss",
+                    None,
+                    vec!["Tech", "News", "Linux"]
+                ),
+                ("https://plain.org/rss", Some("Plain"), vec![]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,447 @@
+use std::error::Error;
+use std::io::{BufWriter, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ammonia::UrlRelative;
+use atom_syndication as atom;
+use chrono::DateTime;
+use colored::Colorize;
+use serde::Serialize;
+use url::{form_urlencoded, Url};
+
+use crate::config::{Order, OutputFormat};
+use crate::dedupe;
+use crate::item::DisplayItem;
+
+/// Flat, serializable view of a DisplayItem for machine readable output
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    channel: &'a str,
+    alias: Option<&'a str>,
+    title: Option<&'a str>,
+    link: Option<&'a str>,
+    description: Option<&'a str>,
+    author: Option<&'a str>,
+    /// RFC 3339 timestamp
+    date: Option<String>,
+    enclosure: Option<&'a str>,
+    /// Other channels the item appeared in, comma separated
+    also_in: String,
+}
+
+/// Names of the fields of [`Record`], in order
+const CSV_HEADER: [&str; 9] = [
+    "channel",
+    "alias",
+    "title",
+    "link",
+    "description",
+    "author",
+    "date",
+    "enclosure",
+    "also_in",
+];
+
+impl<'a> From<&'a DisplayItem> for Record<'a> {
+    fn from(item: &'a DisplayItem) -> Self {
+        Record {
+            channel: &item.chan_title,
+            alias: item.chan_alias.as_deref(),
+            title: item.title.as_deref(),
+            link: item.link.as_deref(),
+            description: item.description.as_deref(),
+            author: item.author.as_deref(),
+            date: item.pub_date.map(|d| d.to_rfc3339()),
+            enclosure: item.enclosure_url.as_deref(),
+            also_in: item.also_in(),
+        }
+    }
+}
+
+/// Write `items` to `out` in the given format.
+/// `order` decides how items are grouped in formats that support it.
+pub fn write(
+    items: &[DisplayItem],
+    format: OutputFormat,
+    order: Order,
+    out: impl Write,
+) -> Result<(), Box<dyn Error>> {
+    let mut out = BufWriter::new(out);
+    match format {
+        OutputFormat::Text => {
+            let by_channel = matches!(order, Order::Channel);
+            let mut current_chan = None;
+            let mut current_group = None;
+            for item in items {
+                // channels are grouped under a header per group, once there are groups
+                let group = match (&item.chan_group, current_group) {
+                    (Some(group), _) => Some(group.as_str()),
+                    (None, Some(_)) => Some(UNGROUPED),
+                    (None, None) => None,
+                };
+                if by_channel
+                    && let Some(name) = group
+                    && group != current_group
+                {
+                    writeln!(out, "{}\n", format!(" {} ", name).bold().reversed())?;
+                    current_group = group;
+                }
+                // items are grouped under a single header per channel
+                if by_channel && current_chan != Some(&item.chan_url) {
+                    writeln!(out, "{}\n", item.styled_chan_label().bold().underline())?;
+                    current_chan = Some(&item.chan_url);
+                }
+                match item.format(!by_channel) {
+                    Ok(output) => writeln!(out, "{}", output)?,
+                    Err(err) => eprintln!("Could not format RSS Item: {}", err),
+                }
+            }
+        }
+        OutputFormat::Json => {
+            let records: Vec<Record> = items.iter().map(Record::from).collect();
+            serde_json::to_writer_pretty(&mut out, &records)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Ndjson => {
+            for item in items {
+                serde_json::to_writer(&mut out, &Record::from(item))?;
+                writeln!(out)?;
+            }
+        }
+        OutputFormat::Csv => {
+            // the header is written up front, so that it is there without items too
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(&mut out);
+            writer.write_record(CSV_HEADER)?;
+            for item in items {
+                writer.serialize(Record::from(item))?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Rss => {
+            aggregate_rss(items).write_to(&mut out)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Atom => {
+            aggregate_atom(items).write_to(&mut out)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Html => write_html(items, order, &mut out)?,
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Title of aggregated feeds
+const AGGREGATE_TITLE: &str = "rssget";
+/// Description of aggregated feeds
+const AGGREGATE_DESCRIPTION: &str = "Items aggregated by rssget";
+/// Header of the channels without a group, listed after the grouped ones
+const UNGROUPED: &str = "Ungrouped";
+
+/// Merge `items` into a single RSS channel, recording each item's source channel
+fn aggregate_rss(items: &[DisplayItem]) -> rss::Channel {
+    rss::Channel {
+        title: AGGREGATE_TITLE.to_string(),
+        link: env!("CARGO_PKG_REPOSITORY").to_string(),
+        description: AGGREGATE_DESCRIPTION.to_string(),
+        generator: Some(format!("rssget {}", env!("CARGO_PKG_VERSION"))),
+        items: items
+            .iter()
+            .map(|item| rss::Item {
+                title: item.title.clone(),
+                link: item.link.clone(),
+                description: item.description.clone(),
+                author: item.author.clone(),
+                guid: item.id.as_deref().map(|id| rss::Guid {
+                    value: global_id(item, id),
+                    permalink: false,
+                }),
+                pub_date: item.pub_date.map(|d| d.to_rfc2822()),
+                enclosure: item.enclosure_url.clone().map(|url| rss::Enclosure {
+                    url,
+                    length: "0".to_string(),
+                    mime_type: "application/octet-stream".to_string(),
+                }),
+                source: is_web_link(&item.chan_url).then(|| rss::Source {
+                    url: item.chan_url.clone(),
+                    title: Some(item.chan_name().to_string()),
+                }),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Merge `items` into a single Atom feed, recording each item's source channel
+fn aggregate_atom(items: &[DisplayItem]) -> atom::Feed {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .and_then(|d| DateTime::from_timestamp(d.as_secs() as i64, 0))
+        .unwrap_or_default()
+        .fixed_offset();
+    let link = |href: &str, rel: &str| atom::Link {
+        href: href.to_string(),
+        rel: rel.to_string(),
+        ..Default::default()
+    };
+    atom::Feed {
+        title: atom::Text::plain(AGGREGATE_TITLE),
+        id: env!("CARGO_PKG_REPOSITORY").to_string(),
+        updated: now,
+        subtitle: Some(atom::Text::plain(AGGREGATE_DESCRIPTION)),
+        // entries without an author of their own take this one
+        authors: vec![atom::Person {
+            name: "rssget".to_string(),
+            ..Default::default()
+        }],
+        generator: Some(atom::Generator {
+            value: "rssget".to_string(),
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            ..Default::default()
+        }),
+        entries: items
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| {
+                let updated = item.pub_date.unwrap_or(now);
+                atom::Entry {
+                    title: atom::Text::plain(item.title.clone().unwrap_or_default()),
+                    id: global_id(item, item.id.as_deref().unwrap_or(&idx.to_string())),
+                    updated,
+                    published: item.pub_date,
+                    authors: item
+                        .author
+                        .iter()
+                        .map(|name| atom::Person {
+                            name: name.clone(),
+                            ..Default::default()
+                        })
+                        .collect(),
+                    links: item
+                        .link
+                        .iter()
+                        .map(|href| link(href, "alternate"))
+                        .chain(
+                            item.enclosure_url
+                                .iter()
+                                .map(|href| link(href, "enclosure")),
+                        )
+                        .collect(),
+                    summary: item.description.clone().map(|desc| {
+                        if item.description_is_html {
+                            atom::Text::html(desc)
+                        } else {
+                            atom::Text::plain(desc)
+                        }
+                    }),
+                    source: is_web_link(&item.chan_url).then(|| atom::Source {
+                        title: atom::Text::plain(item.chan_name()),
+                        id: item.chan_url.clone(),
+                        updated,
+                        links: vec![link(&item.chan_url, "self")],
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// An id for `item` that is unique across channels: `id` itself if it is
+/// globally unique, else `id` as fragment of the channel url, or of a `tag:`
+/// URI for channels read from a command or stdin
+fn global_id(item: &DisplayItem, id: &str) -> String {
+    if dedupe::is_global_id(item, id) {
+        return id.to_string();
+    }
+    let mut url = Url::parse(&item.chan_url)
+        .or_else(|_| {
+            let location: String =
+                form_urlencoded::byte_serialize(item.chan_url.as_bytes()).collect();
+            Url::parse(&format!("tag:rssget,2024:{}", location))
+        })
+        .expect("channel tag URI is valid");
+    url.set_fragment(Some(id));
+    url.to_string()
+}
+
+const HTML_STYLE: &str = "
+body { max-width: 50em; margin: 0 auto; padding: 1em; font-family: sans-serif; line-height: 1.5; }
+h2 { border-bottom: 1px solid #ccc; }
+article { margin-bottom: 1.5em; }
+.meta { color: #666; font-size: 0.9em; }
+img { max-width: 100%; height: auto; }
+";
+
+/// Write `items` as a self-contained HTML digest, grouped by channel or by day
+/// unless ordered by title
+fn write_html(items: &[DisplayItem], order: Order, out: &mut impl Write) -> std::io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>{}</title>", AGGREGATE_TITLE)?;
+    writeln!(out, "<style>{}</style>\n</head>\n<body>", HTML_STYLE)?;
+    writeln!(out, "<h1>{}</h1>", AGGREGATE_TITLE)?;
+
+    let group_of = |item: &DisplayItem| match order {
+        Order::Channel => Some(match &item.chan_group {
+            Some(group) => format!("{} / {}", group, item.chan_label()),
+            None => item.chan_label(),
+        }),
+        Order::Date => Some(
+            item.pub_date
+                // by UTC day, since items with different offsets are sorted by instant
+                .map(|d| d.to_utc().date_naive().to_string())
+                .unwrap_or_else(|| "Undated".to_string()),
+        ),
+        Order::Title => None,
+    };
+    let mut current_group = None;
+    for item in items {
+        if let Some(group) = group_of(item)
+            && current_group.as_ref() != Some(&group)
+        {
+            if current_group.is_some() {
+                writeln!(out, "</section>")?;
+            }
+            writeln!(out, "<section>\n<h2>{}</h2>", escape_html(&group))?;
+            current_group = Some(group);
+        }
+        write_html_item(item, out)?;
+    }
+    if current_group.is_some() {
+        writeln!(out, "</section>")?;
+    }
+    writeln!(out, "</body>\n</html>")
+}
+
+fn write_html_item(item: &DisplayItem, out: &mut impl Write) -> std::io::Result<()> {
+    let conf = &item.conf;
+    writeln!(out, "<article>")?;
+    if let Some(title) = item.title.as_ref().filter(|_| !conf.hide_title) {
+        match item
+            .link
+            .as_deref()
+            .filter(|l| !conf.hide_link && is_web_link(l))
+        {
+            Some(link) => writeln!(
+                out,
+                "<h3><a href=\"{}\">{}</a></h3>",
+                escape_html(link),
+                escape_html(title)
+            )?,
+            None => writeln!(out, "<h3>{}</h3>", escape_html(title))?,
+        }
+    }
+    write!(out, "<p class=\"meta\">{}", escape_html(&item.chan_label()))?;
+    if let Some(pub_date) = item.pub_date.filter(|_| !conf.hide_pub_date) {
+        write!(out, " &middot; {}", pub_date.naive_local())?;
+    }
+    if let Some(author) = item.author.as_ref().filter(|_| !conf.hide_author) {
+        write!(out, " &middot; {}", escape_html(author))?;
+    }
+    if !item.duplicates.is_empty() {
+        write!(out, " &middot; also in {}", escape_html(&item.also_in()))?;
+    }
+    writeln!(out, "</p>")?;
+    if let Some(desc) = item.description.as_ref().filter(|_| !conf.hide_description) {
+        writeln!(out, "<div>{}</div>", description_html(item, desc))?;
+    }
+    if let Some(enclosure_url) = item
+        .enclosure_url
+        .as_deref()
+        .filter(|l| conf.show_enclosure && is_web_link(l))
+    {
+        let enclosure_url = escape_html(enclosure_url);
+        writeln!(
+            out,
+            "<p><a href=\"{}\">{}</a></p>",
+            enclosure_url, enclosure_url
+        )?;
+    }
+    writeln!(out, "</article>")
+}
+
+/// The description `desc` of `item` as HTML: plain text is escaped, keeping
+/// its line breaks, and HTML is sanitized with relative links resolved against
+/// the item link or channel url so they work outside of the feed
+fn description_html(item: &DisplayItem, desc: &str) -> String {
+    if !item.description_is_html {
+        return escape_html(desc).replace('\n', "<br>\n");
+    }
+    let base = [item.link.as_deref(), Some(&item.chan_url)]
+        .into_iter()
+        .flatten()
+        .filter(|url| is_web_link(url))
+        .find_map(|url| Url::parse(url).ok());
+    let mut builder = ammonia::Builder::default();
+    if let Some(base) = base {
+        builder.url_relative(UrlRelative::RewriteWithBase(base));
+    }
+    builder.clean(desc).to_string()
+}
+
+/// Only link to web pages, never to e.g. `javascript:` urls
+fn is_web_link(link: &str) -> bool {
+    link.starts_with("https://") || link.starts_with("http://")
+}
+
+/// Escape text for use in HTML content and quoted attribute values
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::item;
+
+    fn html(items: &[DisplayItem]) -> String {
+        let mut out = vec![];
+        write_html(items, Order::Title, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn html_escapes_plain_text_descriptions() {
+        let plain = DisplayItem {
+            description: Some("Use Vec<String> & HashMap<K, V>\nfor this".to_string()),
+            ..item("a", "plain")
+        };
+        assert!(html(&[plain])
+            .contains("<div>Use Vec&lt;String&gt; &amp; HashMap&lt;K, V&gt;<br>\nfor this</div>"));
+    }
+
+    #[test]
+    fn html_sanitizes_html_descriptions_and_resolves_links() {
+        let rich = DisplayItem {
+            description: Some(r#"<a href="/rel">x</a><script>alert(1)</script>"#.to_string()),
+            description_is_html: true,
+            link: Some("https://example.com/posts/1".to_string()),
+            ..item("a", "rich")
+        };
+        let html = html(&[rich]);
+        assert!(
+            html.contains(r#"<a href="https://example.com/rel""#),
+            "{}",
+            html
+        );
+        assert!(!html.contains("<script>"), "{}", html);
+    }
+}
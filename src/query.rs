@@ -0,0 +1,480 @@
+//! A small query language for selecting items, e.g.
+//! `channel = "HN" and age < 2d and title ~ "rust"`.
+//!
+//! Comparisons are combined with `and`, `or`, `not` and parentheses.
+//! Text fields (`channel`, `title`, `description`, `author`, `link` and
+//! `category`) support `=` and `!=`, compared case insensitively, as well as
+//! `~` and `!~` for case insensitive regex matches. `age` takes a duration
+//! like `2d` or `12h`, and `date` takes a date like `2024-01-31` or an
+//! RFC 3339 timestamp, both with `=`, `!=`, `<`, `<=`, `>` and `>=`.
+//! Items that appeared in several channels match `channel` on any of them.
+//! Items without a date never match a comparison on `age` or `date`.
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use regex::{Regex, RegexBuilder};
+
+use crate::item::DisplayItem;
+
+/// A parsed query
+#[derive(Debug)]
+pub struct Query(Expr);
+
+#[derive(Debug)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Text {
+        field: TextField,
+        negated: bool,
+        pattern: TextPattern,
+    },
+    Age(Op, Duration),
+    Date(Op, DateValue),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TextField {
+    Channel,
+    Title,
+    Description,
+    Author,
+    Link,
+    Category,
+}
+
+#[derive(Debug)]
+enum TextPattern {
+    /// Lowercase text, compared case insensitively
+    Equals(String),
+    Regex(Regex),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Match,
+    NotMatch,
+}
+
+#[derive(Debug)]
+enum DateValue {
+    /// A whole day, compared with the day an item was published
+    Day(NaiveDate),
+    Instant(DateTime<FixedOffset>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Op(Op),
+    /// A quoted string
+    Str(String),
+    /// A bare word, e.g. a field name, keyword or duration
+    Word(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+            Token::Op(op) => write!(f, "{}", op.symbol()),
+            Token::Str(s) => write!(f, "{:?}", s),
+            Token::Word(w) => write!(f, "{}", w),
+        }
+    }
+}
+
+impl Op {
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Match => "~",
+            Op::NotMatch => "!~",
+        }
+    }
+
+    /// Whether `ordering`, of an item's value to the query's value, satisfies this operator
+    fn accepts(self, ordering: Ordering) -> bool {
+        match self {
+            Op::Eq => ordering.is_eq(),
+            Op::Ne => ordering.is_ne(),
+            Op::Lt => ordering.is_lt(),
+            Op::Le => ordering.is_le(),
+            Op::Gt => ordering.is_gt(),
+            Op::Ge => ordering.is_ge(),
+            Op::Match | Op::NotMatch => false,
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        match parser.next() {
+            None => Ok(Query(expr)),
+            Some(token) => Err(format!("Invalid filter: unexpected {}", token)),
+        }
+    }
+}
+
+impl Query {
+    /// Whether `item` is selected by this query, at time `now`
+    pub fn matches(&self, item: &DisplayItem, now: DateTime<Utc>) -> bool {
+        self.0.matches(item, now)
+    }
+}
+
+impl Expr {
+    fn matches(&self, item: &DisplayItem, now: DateTime<Utc>) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(item, now) && b.matches(item, now),
+            Expr::Or(a, b) => a.matches(item, now) || b.matches(item, now),
+            Expr::Not(a) => !a.matches(item, now),
+            Expr::Text {
+                field,
+                negated,
+                pattern,
+            } => {
+                let found = text_values(item, *field).iter().any(|value| match pattern {
+                    TextPattern::Equals(text) => value.to_lowercase() == *text,
+                    TextPattern::Regex(regex) => regex.is_match(value),
+                });
+                found != *negated
+            }
+            Expr::Age(op, age) => item.pub_date.is_some_and(|date| {
+                let item_age = (now - date.to_utc()).to_std().unwrap_or_default();
+                op.accepts(item_age.cmp(age))
+            }),
+            Expr::Date(op, value) => item.pub_date.is_some_and(|date| match value {
+                DateValue::Day(day) => op.accepts(date.date_naive().cmp(day)),
+                DateValue::Instant(instant) => op.accepts(date.cmp(instant)),
+            }),
+        }
+    }
+}
+
+fn text_values(item: &DisplayItem, field: TextField) -> Vec<&str> {
+    match field {
+        // an item collapsed from several channels is in any of them
+        TextField::Channel => std::iter::once(item)
+            .chain(&item.duplicates)
+            .flat_map(|i| {
+                [
+                    i.chan_alias.as_deref(),
+                    Some(i.chan_title.as_str()),
+                    Some(i.chan_url.as_str()),
+                ]
+            })
+            .flatten()
+            .collect(),
+        TextField::Title => item.title.as_deref().into_iter().collect(),
+        TextField::Description => item.description.as_deref().into_iter().collect(),
+        TextField::Author => item.author.as_deref().into_iter().collect(),
+        TextField::Link => item.link.as_deref().into_iter().collect(),
+        TextField::Category => item.categories.iter().map(String::as_str).collect(),
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '=' => Token::Op(Op::Eq),
+            '~' => Token::Op(Op::Match),
+            '!' => match chars.next() {
+                Some('=') => Token::Op(Op::Ne),
+                Some('~') => Token::Op(Op::NotMatch),
+                _ => return Err("Invalid filter: expected != or !~".to_string()),
+            },
+            '<' | '>' => {
+                let or_equal = chars.next_if_eq(&'=').is_some();
+                Token::Op(match (c, or_equal) {
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    (_, false) => Op::Gt,
+                    (_, true) => Op::Ge,
+                })
+            }
+            '"' | '\'' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        // keep other escapes, like `\d`, for regexes
+                        Some('\\') => match chars.next_if(|n| *n == c || *n == '\\') {
+                            Some(escaped) => text.push(escaped),
+                            None => text.push('\\'),
+                        },
+                        Some(end) if end == c => break,
+                        Some(other) => text.push(other),
+                        None => return Err("Invalid filter: unterminated string".to_string()),
+                    }
+                }
+                Token::Str(text)
+            }
+            c => {
+                let mut word = String::from(c);
+                while let Some(next) =
+                    chars.next_if(|n| !n.is_whitespace() && !"()=!~<>\"'".contains(*n))
+                {
+                    word.push(next);
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Consume the next token if it is the keyword `keyword`
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.tokens.get(self.pos) {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.not()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        match self.next() {
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err("Invalid filter: expected )".to_string()),
+                }
+            }
+            Some(Token::Word(field)) => self.comparison(&field),
+            Some(token) => Err(format!("Invalid filter: expected a field, found {}", token)),
+            None => Err("Invalid filter: expected a field".to_string()),
+        }
+    }
+
+    fn comparison(&mut self, field: &str) -> Result<Expr, String> {
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            _ => {
+                return Err(format!(
+                    "Invalid filter: expected an operator after {}",
+                    field
+                ))
+            }
+        };
+        let value = match self.next() {
+            Some(Token::Str(value) | Token::Word(value)) => value,
+            _ => {
+                return Err(format!(
+                    "Invalid filter: expected a value after {} {}",
+                    field,
+                    op.symbol()
+                ))
+            }
+        };
+        let unsupported = || {
+            Err(format!(
+                "Invalid filter: {} does not apply to {}",
+                op.symbol(),
+                field
+            ))
+        };
+
+        let text_field = match field.to_ascii_lowercase().as_str() {
+            "channel" => TextField::Channel,
+            "title" => TextField::Title,
+            "description" => TextField::Description,
+            "author" => TextField::Author,
+            "link" => TextField::Link,
+            "category" => TextField::Category,
+            "age" => {
+                if matches!(op, Op::Match | Op::NotMatch) {
+                    return unsupported();
+                }
+                let age = humantime::parse_duration(&value)
+                    .map_err(|err| format!("Invalid filter: age {}: [{}]", value, err))?;
+                return Ok(Expr::Age(op, age));
+            }
+            "date" => {
+                if matches!(op, Op::Match | Op::NotMatch) {
+                    return unsupported();
+                }
+                let date = match NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+                    Ok(day) => DateValue::Day(day),
+                    Err(_) => DateValue::Instant(
+                        DateTime::parse_from_rfc3339(&value)
+                            .map_err(|err| format!("Invalid filter: date {}: [{}]", value, err))?,
+                    ),
+                };
+                return Ok(Expr::Date(op, date));
+            }
+            _ => return Err(format!("Invalid filter: unknown field {}", field)),
+        };
+        let (negated, pattern) = match op {
+            Op::Eq | Op::Ne => (op == Op::Ne, TextPattern::Equals(value.to_lowercase())),
+            Op::Match | Op::NotMatch => {
+                let regex = RegexBuilder::new(&value)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|err| format!("Invalid filter: regex {}: [{}]", value, err))?;
+                (op == Op::NotMatch, TextPattern::Regex(regex))
+            }
+            _ => return unsupported(),
+        };
+        Ok(Expr::Text {
+            field: text_field,
+            negated,
+            pattern,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::item;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-01-10T12:00:00Z")
+            .unwrap()
+            .to_utc()
+    }
+
+    fn matches(query: &str, item: &DisplayItem) -> bool {
+        query.parse::<Query>().unwrap().matches(item, now())
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let hn = item("HN", "Go news");
+        // parsed as `channel = HN or (title ~ rust and channel = lobsters)`
+        assert!(matches(
+            "channel = HN or title ~ rust and channel = lobsters",
+            &hn
+        ));
+        assert!(!matches(
+            "(channel = HN or title ~ rust) and channel = lobsters",
+            &hn
+        ));
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and() {
+        let hn = item("HN", "Go news");
+        assert!(matches("not title ~ rust and channel = hn", &hn));
+        assert!(!matches("not (title ~ go and channel = hn)", &hn));
+        assert!(matches("not not channel = HN", &hn));
+    }
+
+    #[test]
+    fn quoted_strings_keep_spaces_and_escapes() {
+        let quoted = item("My \"Blog\"", "It's 5\\5");
+        assert!(matches(r#"channel = "my \"blog\"""#, &quoted));
+        assert!(matches(r"title = 'it\'s 5\\5'", &quoted));
+        // other escapes are left for the regex
+        assert!(matches(r#"title ~ "\d\\\\\d""#, &quoted));
+        assert!(!matches(r#"title != "IT'S 5\\5""#, &quoted));
+    }
+
+    #[test]
+    fn age_and_date_compare_dated_items() {
+        let dated = item("HN", "news").dated("2024-01-09T12:00:00Z");
+        assert!(matches("age < 2d", &dated));
+        assert!(!matches("age > 36h", &dated));
+        assert!(matches("date = 2024-01-09", &dated));
+        assert!(matches("date >= 2024-01-09T12:00:00Z", &dated));
+        assert!(!matches("date < 2024-01-09", &dated));
+    }
+
+    #[test]
+    fn age_and_date_never_match_undated_items() {
+        let undated = item("HN", "news");
+        for query in [
+            "age < 2d",
+            "age >= 2d",
+            "date = 2024-01-09",
+            "date != 2024-01-09",
+        ] {
+            assert!(!matches(query, &undated), "{}", query);
+        }
+        assert!(matches("not age < 2d", &undated));
+    }
+
+    #[test]
+    fn invalid_queries_are_errors() {
+        for query in [
+            "",
+            "title",
+            "title =",
+            "title = rust and",
+            "(title = rust",
+            "title = rust)",
+            "size > 3",
+            "title < rust",
+            "age ~ 2d",
+            "age < soon",
+            "date > yesterday",
+            "title ~ \"(\"",
+            "title = \"rust",
+            "title ! rust",
+        ] {
+            assert!(query.parse::<Query>().is_err(), "{}", query);
+        }
+    }
+}
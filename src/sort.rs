@@ -0,0 +1,194 @@
+//! Display ordering of items
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::config::{ChannelOrder, Config, Order};
+use crate::item::DisplayItem;
+
+/// Sort `items` for display according to the ordering options of `config`
+pub fn sort_items(items: &mut [DisplayItem], config: &Config) {
+    let by_date = |a: &DisplayItem, b: &DisplayItem| a.pub_date.cmp(&b.pub_date);
+    let ordered = |ordering: Ordering| {
+        if config.reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    };
+    match config.display_by {
+        Order::Date => items.sort_by(|a, b| ordered(by_date(a, b))),
+        Order::Title => {
+            let title = |i: &DisplayItem| i.title.as_ref().map(|t| t.to_lowercase());
+            items.sort_by(|a, b| ordered(title(a).cmp(&title(b))));
+        }
+        Order::Channel => {
+            // rank the channels by group, ungrouped ones last, then within
+            // each group, tie breaking names by the config order
+            let mut channels: Vec<(Option<&str>, String, &str)> = vec![];
+            for item in items.iter() {
+                if !channels.iter().any(|(_, _, url)| *url == item.chan_url) {
+                    let name = match config.channel_order {
+                        ChannelOrder::Config => String::new(),
+                        ChannelOrder::Name => item.chan_name().to_lowercase(),
+                    };
+                    channels.push((item.chan_group.as_deref(), name, &item.chan_url));
+                }
+            }
+            let position = |url: &str| {
+                config
+                    .channels
+                    .iter()
+                    .position(|c| c.location() == url)
+                    .unwrap_or(usize::MAX)
+            };
+            let group_rank = |group: Option<&str>| match group {
+                None => (true, String::new(), 0),
+                Some(group) => match config.channel_order {
+                    ChannelOrder::Config => (
+                        false,
+                        String::new(),
+                        config
+                            .channels
+                            .iter()
+                            .position(|c| c.groups.first().is_some_and(|g| g == group))
+                            .unwrap_or(usize::MAX),
+                    ),
+                    ChannelOrder::Name => (false, group.to_lowercase(), 0),
+                },
+            };
+            channels.sort_by_cached_key(|(group, name, url)| {
+                (group_rank(*group), name.clone(), position(url))
+            });
+            let rank: HashMap<String, usize> = channels
+                .into_iter()
+                .enumerate()
+                .map(|(rank, (_, _, url))| (url.to_owned(), rank))
+                .collect();
+            items.sort_by(|a, b| {
+                rank[&a.chan_url]
+                    .cmp(&rank[&b.chan_url])
+                    .then_with(|| ordered(by_date(a, b)))
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ChanConfig;
+    use crate::item::{item, titles};
+
+    /// Channels, by url and groups, in config order
+    const CHANNELS: [(&str, &[&str]); 5] = [
+        ("zeta", &["work"]),
+        ("alpha", &[]),
+        ("mike", &["news", "work"]),
+        ("beta", &["work"]),
+        ("delta", &[]),
+    ];
+
+    fn config(display_by: Order, channel_order: ChannelOrder, reverse: bool) -> Config {
+        Config {
+            display_by,
+            channel_order,
+            reverse,
+            channels: CHANNELS
+                .iter()
+                .map(|(url, groups)| ChanConfig {
+                    url: url.to_string(),
+                    groups: groups.iter().map(|g| g.to_string()).collect(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Two items of each channel, titled after it, in feed order
+    fn items() -> Vec<DisplayItem> {
+        CHANNELS
+            .iter()
+            .flat_map(|(chan, groups)| {
+                [("2", "2024-01-02T00:00:00Z"), ("1", "2024-01-01T00:00:00Z")].map(|(n, date)| {
+                    DisplayItem {
+                        chan_group: groups.first().map(|g| g.to_string()),
+                        ..item(chan, &format!("{} {}", chan, n)).dated(date)
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn sorted(config: &Config) -> Vec<String> {
+        let mut items = items();
+        sort_items(&mut items, config);
+        titles(&items).into_iter().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn config_order_ranks_groups_by_their_first_channel() {
+        let config = config(Order::Channel, ChannelOrder::Config, false);
+        assert_eq!(
+            sorted(&config),
+            [
+                "zeta 1", "zeta 2", "beta 1", "beta 2", "mike 1", "mike 2", "alpha 1", "alpha 2",
+                "delta 1", "delta 2"
+            ]
+        );
+    }
+
+    #[test]
+    fn name_order_ranks_groups_and_channels_by_name() {
+        let config = config(Order::Channel, ChannelOrder::Name, false);
+        assert_eq!(
+            sorted(&config),
+            [
+                "mike 1", "mike 2", "beta 1", "beta 2", "zeta 1", "zeta 2", "alpha 1", "alpha 2",
+                "delta 1", "delta 2"
+            ]
+        );
+    }
+
+    #[test]
+    fn reverse_only_reverses_items_within_channels() {
+        let config = config(Order::Channel, ChannelOrder::Name, true);
+        assert_eq!(
+            sorted(&config),
+            [
+                "mike 2", "mike 1", "beta 2", "beta 1", "zeta 2", "zeta 1", "alpha 2", "alpha 1",
+                "delta 2", "delta 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn equal_names_keep_the_config_order() {
+        let mut config = config(Order::Channel, ChannelOrder::Name, false);
+        let mut items = items();
+        // `delta` takes the name of `alpha`, which comes first in the config
+        for item in items.iter_mut().filter(|i| i.chan_url == "delta") {
+            item.chan_alias = Some("Alpha".to_string());
+        }
+        sort_items(&mut items, &config);
+        assert_eq!(
+            titles(&items)[6..],
+            ["alpha 1", "alpha 2", "delta 1", "delta 2"]
+        );
+        // and the other way around
+        config.channels.swap(1, 4);
+        sort_items(&mut items, &config);
+        assert_eq!(
+            titles(&items)[6..],
+            ["delta 1", "delta 2", "alpha 1", "alpha 2"]
+        );
+    }
+
+    #[test]
+    fn date_and_title_order_ignore_channels() {
+        let by_date = config(Order::Date, ChannelOrder::Config, false);
+        assert!(sorted(&by_date)[..5].iter().all(|t| t.ends_with('1')));
+        let by_title = config(Order::Title, ChannelOrder::Config, true);
+        assert_eq!(sorted(&by_title)[..3], ["zeta 2", "zeta 1", "mike 2"]);
+    }
+}
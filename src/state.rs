@@ -0,0 +1,129 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::item::DisplayItem;
+
+/// Ids of the items currently in each channel, keyed by channel url
+pub type FeedIds = HashMap<String, HashSet<String>>;
+
+/// Ids of items that have already been read, keyed by channel url
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct ReadState {
+    channels: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl ReadState {
+    /// Location of the read state file in the system data directory
+    fn path() -> PathBuf {
+        dirs::data_dir()
+            .map(|mut d| {
+                d.push("rssget/read.yaml");
+                d
+            })
+            .expect("could not determine system data directory")
+    }
+
+    /// Load the read state from disk, or an empty one if none was saved yet
+    pub fn load() -> Result<ReadState, Box<dyn Error>> {
+        match OpenOptions::new().read(true).open(Self::path()) {
+            Ok(file) => Ok(serde_yaml::from_reader(&file)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(ReadState::default()),
+            Err(err) => Err(Box::new(err)),
+        }
+    }
+
+    /// Write the read state to disk
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // write to a temporary file first so an interrupted run can't corrupt the state
+        let tmp = path.with_extension("yaml.tmp");
+        fs::write(&tmp, serde_yaml::to_string(self)?)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    /// Whether `item`, or any of its duplicates in other channels, was marked
+    /// as read in a previous run.
+    /// Items without any identifier are never considered read.
+    pub fn is_read(&self, item: &DisplayItem) -> bool {
+        item.id.as_ref().is_some_and(|id| {
+            self.channels
+                .get(&item.chan_url)
+                .is_some_and(|ids| ids.contains(id))
+        }) || item.duplicates.iter().any(|d| self.is_read(d))
+    }
+
+    /// Remember `item`, along with its duplicates, as read
+    pub fn mark_read(&mut self, item: &DisplayItem) {
+        if let Some(id) = &item.id {
+            self.channels
+                .entry(item.chan_url.clone())
+                .or_default()
+                .insert(id.clone());
+        }
+        item.duplicates.iter().for_each(|d| self.mark_read(d));
+    }
+
+    /// Forget the read items that are no longer in their channel, so the state
+    /// doesn't grow without bound. Channels missing from `feed_ids`, e.g.
+    /// because they could not be fetched, are kept as they are.
+    pub fn forget_removed(&mut self, feed_ids: &FeedIds) {
+        for (url, ids) in &mut self.channels {
+            if let Some(current) = feed_ids.get(url) {
+                ids.retain(|id| current.contains(id));
+            }
+        }
+        self.channels.retain(|_, ids| !ids.is_empty());
+    }
+
+    /// Forget that `item`, or any of its duplicates, was read
+    pub fn mark_unread(&mut self, item: &DisplayItem) {
+        if let Some(id) = &item.id
+            && let Some(ids) = self.channels.get_mut(&item.chan_url)
+        {
+            ids.remove(id);
+        }
+        item.duplicates.iter().for_each(|d| self.mark_unread(d));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids<T: FromIterator<String>>(ids: &[&str]) -> T {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn forget_removed_only_prunes_fetched_channels() {
+        let mut state = ReadState {
+            channels: BTreeMap::from([
+                ("fetched".to_string(), ids(&["old", "kept"])),
+                ("emptied".to_string(), ids(&["old"])),
+                ("offline".to_string(), ids(&["old"])),
+            ]),
+        };
+        let feed_ids = FeedIds::from([
+            ("fetched".to_string(), ids(&["kept", "new"])),
+            ("emptied".to_string(), ids(&["new"])),
+        ]);
+        state.forget_removed(&feed_ids);
+        assert_eq!(
+            state.channels,
+            BTreeMap::from([
+                ("fetched".to_string(), ids(&["kept"])),
+                ("offline".to_string(), ids(&["old"])),
+            ])
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use std::error::Error;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::item::{description_text, DisplayItem};
+use crate::state::ReadState;
+
+const HELP: &str =
+    "j/k: move  J/K: scroll preview  c/C: next/prev channel  a: all channels  m: toggle read  M: mark all read  q: quit";
+
+/// State of the interactive reader
+struct App {
+    items: Vec<DisplayItem>,
+    read_state: ReadState,
+    /// Distinct channels in order of first appearance, as (url, title)
+    channels: Vec<(String, String)>,
+    /// Index into `channels` of the channel being shown, or all if `None`
+    channel_filter: Option<usize>,
+    /// Indices into `items` that pass the channel filter
+    visible: Vec<usize>,
+    list_state: ListState,
+    preview_scroll: u16,
+}
+
+impl App {
+    fn new(items: Vec<DisplayItem>, read_state: ReadState) -> App {
+        let mut channels: Vec<(String, String)> = vec![];
+        for item in &items {
+            if !channels.iter().any(|(url, _)| *url == item.chan_url) {
+                channels.push((item.chan_url.clone(), item.chan_label()));
+            }
+        }
+        let mut app = App {
+            items,
+            read_state,
+            channels,
+            channel_filter: None,
+            visible: vec![],
+            list_state: ListState::default(),
+            preview_scroll: 0,
+        };
+        app.apply_filter();
+        app
+    }
+
+    /// Recompute the visible items after the channel filter changed
+    fn apply_filter(&mut self) {
+        let chan_url = self.channel_filter.map(|idx| &self.channels[idx].0);
+        self.visible = (0..self.items.len())
+            .filter(|idx| chan_url.is_none_or(|url| self.items[*idx].chan_url == *url))
+            .collect();
+        self.list_state
+            .select((!self.visible.is_empty()).then_some(0));
+        self.preview_scroll = 0;
+    }
+
+    fn selected(&self) -> Option<&DisplayItem> {
+        self.list_state
+            .selected()
+            .and_then(|idx| self.visible.get(idx))
+            .map(|idx| &self.items[*idx])
+    }
+
+    fn select_next(&mut self) {
+        self.list_state.select_next();
+        self.preview_scroll = 0;
+    }
+
+    fn select_previous(&mut self) {
+        self.list_state.select_previous();
+        self.preview_scroll = 0;
+    }
+
+    /// Cycle the channel filter forwards or backwards, passing through "all channels"
+    fn cycle_channel(&mut self, forward: bool) {
+        let len = self.channels.len();
+        self.channel_filter = match (self.channel_filter, forward) {
+            (None, true) => (len > 0).then_some(0),
+            (None, false) => len.checked_sub(1),
+            (Some(idx), true) => (idx + 1 < len).then_some(idx + 1),
+            (Some(idx), false) => idx.checked_sub(1),
+        };
+        self.apply_filter();
+    }
+
+    fn toggle_read(&mut self) {
+        let Some(&idx) = self
+            .list_state
+            .selected()
+            .and_then(|selected| self.visible.get(selected))
+        else {
+            return;
+        };
+        let item = &self.items[idx];
+        if self.read_state.is_read(item) {
+            self.read_state.mark_unread(item);
+        } else {
+            self.read_state.mark_read(item);
+        }
+    }
+
+    fn mark_all_read(&mut self) {
+        for idx in &self.visible {
+            self.read_state.mark_read(&self.items[*idx]);
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [list_area, preview_area, help_area] = Layout::vertical([
+            Constraint::Percentage(40),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let rows: Vec<ListItem> = self
+            .visible
+            .iter()
+            .map(|idx| {
+                let item = &self.items[*idx];
+                let date = item
+                    .pub_date
+                    .map(|d| d.naive_local().format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| " ".repeat(16));
+                let line = Line::from(vec![
+                    Span::raw(date).bold(),
+                    Span::raw(" "),
+                    chan_span(item),
+                    Span::raw(" "),
+                    Span::raw(item.title.clone().unwrap_or_default()),
+                ]);
+                if self.read_state.is_read(item) {
+                    ListItem::new(line).dim()
+                } else {
+                    ListItem::new(line)
+                }
+            })
+            .collect();
+        let list_title = match self.channel_filter {
+            Some(idx) => format!(" {} ", self.channels[idx].1),
+            None => " All channels ".to_string(),
+        };
+        let list = List::new(rows)
+            .block(Block::bordered().title(list_title))
+            .highlight_style(Style::new().reversed())
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, list_area, &mut self.list_state);
+
+        let preview = self
+            .selected()
+            .map(|item| preview_text(item, preview_area))
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(preview)
+                .block(Block::bordered())
+                .wrap(Wrap { trim: false })
+                .scroll((self.preview_scroll, 0)),
+            preview_area,
+        );
+
+        frame.render_widget(Line::from(HELP).dim(), help_area);
+    }
+}
+
+/// The channel name of `item` in the channel's color, green by default
+fn chan_span(item: &DisplayItem) -> Span<'static> {
+    use colored::Color as Term;
+    let color = match item.chan_color {
+        None => Color::Green,
+        Some(Term::Black) => Color::Black,
+        Some(Term::Red) => Color::Red,
+        Some(Term::Green) => Color::Green,
+        Some(Term::Yellow) => Color::Yellow,
+        Some(Term::Blue) => Color::Blue,
+        Some(Term::Magenta) => Color::Magenta,
+        Some(Term::Cyan) => Color::Cyan,
+        Some(Term::White) => Color::Gray,
+        Some(Term::BrightBlack) => Color::DarkGray,
+        Some(Term::BrightRed) => Color::LightRed,
+        Some(Term::BrightGreen) => Color::LightGreen,
+        Some(Term::BrightYellow) => Color::LightYellow,
+        Some(Term::BrightBlue) => Color::LightBlue,
+        Some(Term::BrightMagenta) => Color::LightMagenta,
+        Some(Term::BrightCyan) => Color::LightCyan,
+        Some(Term::BrightWhite) => Color::White,
+        Some(Term::TrueColor { r, g, b }) => Color::Rgb(r, g, b),
+    };
+    Span::raw(item.chan_label()).fg(color)
+}
+
+/// Build the preview pane contents for `item`, sized to fit `area`
+fn preview_text(item: &DisplayItem, area: Rect) -> Text<'static> {
+    let mut text = Text::default();
+    if let Some(title) = &item.title {
+        text.push_line(Line::from(title.clone()).bold());
+    }
+    let mut meta = vec![chan_span(item)];
+    if let Some(pub_date) = item.pub_date {
+        meta.push(Span::raw(format!(" - {}", pub_date.naive_local())));
+    }
+    if let Some(author) = &item.author {
+        meta.push(Span::raw(format!(" - {}", author)));
+    }
+    if !item.duplicates.is_empty() {
+        meta.push(Span::raw(format!(" (also in {})", item.also_in())).dim());
+    }
+    text.push_line(Line::from(meta));
+    if let Some(link) = &item.link {
+        text.push_line(Line::from(link.clone()).blue());
+    }
+    if let Some(enclosure_url) = &item.enclosure_url {
+        text.push_line(Line::from(format!("[{}]", enclosure_url)));
+    }
+    if let Some(desc) = &item.description {
+        text.push_line(Line::default());
+        // leave room for the block borders
+        let width = usize::from(area.width.saturating_sub(2)).max(20);
+        for line in description_text(desc, item.description_is_html, width).lines() {
+            text.push_line(Line::from(line.to_string()));
+        }
+    }
+    text
+}
+
+/// Run the interactive reader until the user quits, then save the read state
+pub fn run(items: Vec<DisplayItem>, read_state: ReadState) -> Result<(), Box<dyn Error>> {
+    let mut app = App::new(items, read_state);
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+    result?;
+    app.read_state.save()
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> Result<(), Box<dyn Error>> {
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('j') | KeyCode::Down => app.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => app.select_previous(),
+            KeyCode::Char('g') | KeyCode::Home => app.list_state.select_first(),
+            KeyCode::Char('G') | KeyCode::End => app.list_state.select_last(),
+            KeyCode::Char('J') | KeyCode::PageDown => {
+                app.preview_scroll = app.preview_scroll.saturating_add(5)
+            }
+            KeyCode::Char('K') | KeyCode::PageUp => {
+                app.preview_scroll = app.preview_scroll.saturating_sub(5)
+            }
+            KeyCode::Char('c') => app.cycle_channel(true),
+            KeyCode::Char('C') => app.cycle_channel(false),
+            KeyCode::Char('a') => {
+                app.channel_filter = None;
+                app.apply_filter();
+            }
+            KeyCode::Char('m') => app.toggle_read(),
+            KeyCode::Char('M') => app.mark_all_read(),
+            _ => {}
+        }
+    }
+}